@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::framework::standard::{CommandResult, macros::command, Args};
+use crate::helpers::airport_database::{AirportDatabase, AirportDatabaseError};
+use crate::helpers::global_data::AirportDatabaseContainer;
+
+#[command]
+#[owners_only]
+#[num_args(1)]
+#[usage = "path"]
+#[description = "Imports the OurAirports airports.csv, runways.csv, airport-frequencies.csv and navaids.csv files in a directory on the host"]
+async fn import(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let source = PathBuf::from(args.rest());
+    if !source.is_dir() {
+        msg.channel_id.say(ctx, format!(":no_entry_sign: `{}` isn't a directory.", source.display())).await?;
+        return Ok(());
+    }
+
+    msg.channel_id.say(ctx, ":hourglass: Importing, This can take a while.").await?;
+    replace_airport_database(ctx, msg, AirportDatabase::import(source).await).await
+}
+
+#[command]
+#[owners_only]
+#[description = "Reads the OurAirports CSV files in AIRPORT_DATA_DIR again"]
+async fn refresh(ctx: &Context, msg: &Message) -> CommandResult {
+    replace_airport_database(ctx, msg, AirportDatabase::load_from_env().await).await
+}
+
+// Swaps in the new data only once it's fully loaded.
+async fn replace_airport_database(ctx: &Context, msg: &Message, airport_database: Result<AirportDatabase, AirportDatabaseError>) -> CommandResult {
+    let airport_database = match airport_database {
+        Ok(airport_database) => airport_database,
+        Err(why) => { msg.channel_id.say(ctx, format!(":no_entry_sign: {}", why)).await?; return Ok(()); }
+    };
+
+    let reply = format!(":white_check_mark: Loaded {} airports, {} frequencies and {} navaids.",
+                        airport_database.airport_count(), airport_database.frequency_count(), airport_database.navaid_count());
+
+    let airport_database_container = ctx.data.read().await.get::<AirportDatabaseContainer>().cloned().unwrap();
+    *airport_database_container.write().await = airport_database;
+
+    msg.channel_id.say(ctx, reply).await?;
+
+    Ok(())
+}
@@ -1,118 +1,630 @@
 use serenity::prelude::*;
 use serenity::model::prelude::*;
-use serenity::framework::standard::{CommandResult, macros::command, Args};
+use serenity::framework::standard::{CommandResult, CommandError, macros::command, Args};
 use serenity::utils::Colour;
-use urlencoding::encode as url_encode;
-use std::env;
+use std::{fmt, sync::Arc};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
 use crate::models::avwx::{AvwxIcao, AvwxIcaoRunway};
+use crate::models::metar::{Metar, Taf, TafPeriod, Conditions, Wind, Altimeter, DistanceUnit, VisibilityModifier, FlightRules, describe_weather};
 use std::time::Duration;
-use serenity::futures::StreamExt;
 use serenity::builder::CreateEmbed;
-use crate::helpers::global_data::ReqwestContainer;
+use crate::helpers::global_data::{AviationProviderContainer, NotamSourceContainer, AirportDatabaseContainer};
+use crate::models::ourairports::{OurAirportsFrequency, OurAirportsNavaid};
+use crate::helpers::notam_source::NotamSource;
+use crate::models::notam::{Notam, NotamCategory, is_runway_closed};
+use crate::helpers::database_helper::DatabaseUser;
+use crate::helpers::command_source::CommandSource;
+use crate::helpers::paginator::Paginator;
+use crate::models::units::UserUnits;
+use crate::helpers::aviation_provider::{AviationProvider, ProviderError};
+use crate::helpers::aviation_helper::{wind_components, great_circle, format_enroute_time, GreatCircle, StationFilter, KILOMETERS_PER_NAUTICAL_MILE, chart_links,
+                                         pressure_altitude, density_altitude, isa_temperature, DensityAltitudeBand, parse_elevation, parse_temperature, parse_altimeter,
+                                         daylight_times, local_time_zone, DaylightTimes, SunCrossing};
+
+const NEAREST_PER_PAGE: usize = 5;
+const NOTAMS_PER_PAGE: usize = 5;
+// How much better the best airport has to match a search before the others are ignored
+const AMBIGUOUS_SCORE_MARGIN: f64 = 0.05;
+const PICK_TIMEOUT: Duration = Duration::from_secs(60);
+const NUMBER_EMOJIS: [&str; 9] = ["1\u{fe0f}\u{20e3}", "2\u{fe0f}\u{20e3}", "3\u{fe0f}\u{20e3}", "4\u{fe0f}\u{20e3}", "5\u{fe0f}\u{20e3}",
+                                  "6\u{fe0f}\u{20e3}", "7\u{fe0f}\u{20e3}", "8\u{fe0f}\u{20e3}", "9\u{fe0f}\u{20e3}"];
 
 #[command]
-#[num_args(1)]
 #[aliases("ic")]
-#[usage = "ident"]
-async fn icao(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    // Parse the arguments
-    let icao = match args.single::<String>() {
-        Ok(icao) => icao,
-        Err(_) => { msg.channel_id.say(ctx, ":no_entry_sign: Couldn't parse the ICAO ident, Are you sure it's valid?").await?; return Ok(()); }
+#[usage = "[ident, IATA code, name or city]"]
+#[description = "Shows information about an airport, Names and cities are searched in the local airport database"]
+async fn icao(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    // Parse the arguments, Names can be several words
+    let query = Some(args.rest().trim().to_string()).filter(|query| !query.is_empty());
+
+    run_icao(ctx, &CommandSource::from(msg), query).await
+}
+
+pub(crate) async fn run_icao(ctx: &Context, source: &CommandSource<'_>, query: Option<String>) -> CommandResult {
+    let database_user = DatabaseUser::get_or_default(ctx, source.author().id.0 as i64).await;
+    let icao = match ident_or_home(ctx, source, query, &database_user).await? {
+        Some(icao) => icao,
+        None => return Ok(()),
     };
 
-    // Get the reqwest client
-    let reqwest_client  = ctx.data.read().await.get::<ReqwestContainer>().cloned().unwrap();
     // Fetch the data
-    let avwx_response = reqwest_client.get(&format!("https://avwx.rest/api/station/{}?format=json", url_encode(&icao)))
-        .header(reqwest::header::AUTHORIZATION, &format!("Token {}", env::var("AVWX_TOKEN").unwrap()))
-        .send().await?;
-
-    // Deserialize the data
-    let avwx_data = match avwx_response.json::<AvwxIcao>().await {
-        Ok(avwx_data) => avwx_data,
-        Err(why) => {
-            if why.is_decode() {
+    let avwx_data = match resolve_station(ctx, source, &icao).await? {
+        Some(avwx_data) => avwx_data,
+        None => return Ok(()),
+    };
+
+    // The NOTAMs only mark closed runways, So errors are ignored
+    let notams = match get_notam_source(ctx).await {
+        Some(notam_source) => notam_source.notams(&avwx_data.icao).await.unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    // Build the pages, The summary first and then a page per runway.
+    let mut pages = vec![create_icao_embed(&avwx_data, &notams, &database_user.units)];
+    for runway in &avwx_data.runways {
+        pages.push(create_icao_runway_embed(runway, is_runway_closed(runway, &notams), &database_user.units));
+    }
+
+    // Frequencies and navaids come from the local OurAirports data, When it's loaded
+    let airport_database = ctx.data.read().await.get::<AirportDatabaseContainer>().cloned().unwrap();
+    {
+        let airport_database = airport_database.read().await;
+        let frequencies = airport_database.frequencies(&avwx_data.icao);
+        if !frequencies.is_empty() {
+            pages.push(create_icao_frequencies_embed(&avwx_data.icao, frequencies));
+        }
+        let navaids = airport_database.nearby_navaids(&avwx_data.icao, avwx_data.latitude as f64, avwx_data.longitude as f64);
+        if !navaids.is_empty() {
+            pages.push(create_icao_navaids_embed(&avwx_data.icao, &navaids));
+        }
+    }
+    pages.push(create_icao_charts_embed(&avwx_data));
+
+    Paginator::new(pages).send(ctx, source).await
+}
+
+#[command]
+#[usage = "[ident] or raw:<report>"]
+#[description = "Shows the current decoded METAR for an airport, Or decodes a raw METAR without any API"]
+async fn metar(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let metar = if args.rest().to_lowercase().starts_with("raw:") {
+        // Decode the report offline
+        match Metar::parse(&args.rest()[4..]) {
+            Ok(metar) => metar,
+            Err(why) => {
+                msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+                    e.title("An error occurred decoding the METAR.")
+                        .description(format!("```{}```", why))
+                        .color(Colour::RED)
+                })).await?;
+
+                return Ok(())
+            }
+        }
+    } else {
+        // Parse the arguments
+        let icao = match ident_or_home(ctx, &source, args.single::<String>().ok(), &database_user).await? {
+            Some(icao) => icao,
+            None => return Ok(()),
+        };
+
+        // Fetch the data
+        let provider = get_provider(ctx).await;
+        match provider.metar(&icao).await {
+            Ok(metar) => metar,
+            Err(why) => return send_provider_error(ctx, &source, why).await,
+        }
+    };
+
+    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+        e.0 = create_metar_embed(&metar, &database_user.units).0;
+        e
+    })).await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage = "[ident] or raw:<report>"]
+#[description = "Shows the terminal aerodrome forecast for an airport, One page per forecast period"]
+async fn taf(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let taf = if args.rest().to_lowercase().starts_with("raw:") {
+        // Decode the report offline
+        match Taf::parse(&args.rest()[4..]) {
+            Ok(taf) => taf,
+            Err(why) => {
                 msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
-                    e.title("An error occurred decoding AVWX's response.")
+                    e.title("An error occurred decoding the TAF.")
                         .description(format!("```{}```", why))
                         .color(Colour::RED)
                 })).await?;
-            } else {
-                msg.channel_id.say(&ctx.http, ":no_entry_sign: An error occurred fetching from AVWX's API.").await?;
+
+                return Ok(())
             }
+        }
+    } else {
+        // Parse the arguments
+        let icao = match ident_or_home(ctx, &source, args.single::<String>().ok(), &database_user).await? {
+            Some(icao) => icao,
+            None => return Ok(()),
+        };
 
-            return Ok(())
+        // Fetch the data
+        let provider = get_provider(ctx).await;
+        match provider.taf(&icao).await {
+            Ok(taf) => taf,
+            Err(why) => return send_provider_error(ctx, &source, why).await,
         }
     };
 
-    // Position in the menu
-    let mut current_pos = 0;
+    // Build the pages, The full report first and then a page per forecast period.
+    let mut pages = vec![create_taf_embed(&taf)];
+    for period in &taf.periods {
+        pages.push(create_taf_period_embed(&taf, period, &database_user.units));
+    }
 
-    // Send the embed
-    let mut sent_message = msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
-        e.0 = create_icao_embed(current_pos as i32, &avwx_data).0;
+    Paginator::new(pages).send(ctx, &source).await
+}
+
+#[command]
+#[max_args(2)]
+#[aliases("wind", "crosswind")]
+#[usage = "[ident] [crosswind_limit]"]
+#[description = "Works out the head, tail and crosswind on every runway end with the current METAR"]
+async fn winds(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    // Parse the arguments, A number first means the ident was left out
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let ident = match args.current() {
+        Some(argument) if argument.parse::<f32>().is_err() => args.single::<String>().ok(),
+        _ => None,
+    };
+    let icao = match ident_or_home(ctx, &source, ident, &database_user).await? {
+        Some(icao) => icao,
+        None => return Ok(()),
+    };
+    let crosswind_limit = if args.is_empty() {
+        None
+    } else {
+        match args.single::<f32>() {
+            // The limit is given in the user's speed unit
+            Ok(crosswind_limit) => Some(database_user.units.speed_to_knots(crosswind_limit)),
+            Err(_) => {
+                msg.channel_id.say(ctx, format!(":no_entry_sign: The crosswind limit has to be a number in {}.", database_user.units.speed_abbreviation())).await?;
+                return Ok(());
+            }
+        }
+    };
+
+    // Fetch the data
+    let avwx_data = match resolve_station(ctx, &source, &icao).await? {
+        Some(avwx_data) => avwx_data,
+        None => return Ok(()),
+    };
+    let metar = match get_provider(ctx).await.metar(&avwx_data.icao).await {
+        Ok(metar) => metar,
+        Err(why) => return send_provider_error(ctx, &source, why).await,
+    };
+
+    if avwx_data.runways.is_empty() {
+        msg.channel_id.say(ctx, format!(":no_entry_sign: There is no runway data for {}.", avwx_data.icao)).await?;
+        return Ok(());
+    }
+    let wind = match metar.conditions.wind {
+        Some(wind) => wind,
+        None => { msg.channel_id.say(ctx, format!(":no_entry_sign: The METAR for {} doesn't report the wind.", avwx_data.icao)).await?; return Ok(()); }
+    };
+    let wind_direction = match wind.direction {
+        Some(wind_direction) if wind.speed > 0 => wind_direction,
+        _ => {
+            msg.channel_id.say(ctx, format!(":information_source: The wind at {} is {}, So no runway is favoured.",
+                                            avwx_data.icao, format_wind(&metar.conditions.wind, &database_user.units).replace('\n', ", ").to_lowercase())).await?;
+            return Ok(());
+        }
+    };
+
+    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+        e.0 = create_winds_embed(&avwx_data, &wind, wind_direction, crosswind_limit, &database_user.units).0;
         e
     })).await?;
 
-    // React the menu
-    sent_message.react(&ctx.http, ReactionType::Unicode(String::from("⬅"))).await?;
-    sent_message.react(&ctx.http, ReactionType::Unicode(String::from("➡"))).await?;
+    Ok(())
+}
+
+#[command]
+#[min_args(1)]
+#[max_args(3)]
+#[aliases("distance", "dist")]
+#[usage = "[from] to [groundspeed]"]
+#[description = "Works out the great circle distance, Bearings and enroute time between two airports"]
+async fn route(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    // Parse the arguments, With a single ident the route starts at the home airport
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let first_ident = match args.single::<String>() {
+        Ok(first_ident) => first_ident,
+        Err(_) => { msg.channel_id.say(ctx, ":no_entry_sign: Couldn't parse the ICAO idents, Are you sure they're valid?").await?; return Ok(()); }
+    };
+    let second_ident = match args.current() {
+        Some(argument) if argument.parse::<f64>().is_err() => args.single::<String>().ok(),
+        _ => None,
+    };
+    let (from, to) = match second_ident {
+        Some(to) => (first_ident, to),
+        None => match ident_or_home(ctx, &source, None, &database_user).await? {
+            Some(from) => (from, first_ident),
+            None => return Ok(()),
+        },
+    };
+    let groundspeed = if args.is_empty() {
+        None
+    } else {
+        match args.single::<f64>() {
+            // The groundspeed is given in the user's speed unit
+            Ok(groundspeed) if groundspeed > 0.0 => Some(database_user.units.speed_to_knots(groundspeed as f32) as f64),
+            _ => {
+                msg.channel_id.say(ctx, format!(":no_entry_sign: The groundspeed has to be a positive number in {}.", database_user.units.speed_abbreviation())).await?;
+                return Ok(());
+            }
+        }
+    };
+
+    // Fetch the data
+    let from_station = match resolve_station(ctx, &source, &from).await? {
+        Some(from_station) => from_station,
+        None => return Ok(()),
+    };
+    let to_station = match resolve_station(ctx, &source, &to).await? {
+        Some(to_station) => to_station,
+        None => return Ok(()),
+    };
+
+    // The weather is a bonus, So errors are ignored
+    let provider = get_provider(ctx).await;
+    let from_metar = provider.metar(&from_station.icao).await.ok();
+    let to_metar = provider.metar(&to_station.icao).await.ok();
+
+    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+        e.0 = create_route_embed(&from_station, &to_station, groundspeed, &from_metar, &to_metar, &database_user.units).0;
+        e
+    })).await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("near", "closest")]
+#[usage = "[ident|lat,lon] [type:<type>] [runway:<length>[ft|m]] [surface:<surface|paved|unpaved>] [reporting]"]
+#[description = "Finds the closest stations to an airport or coordinates, With optional filters"]
+async fn nearest(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    // Parse the arguments, A filter first means the location was left out
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let location = match args.current() {
+        Some(argument) if !argument.contains(':') && argument != "reporting" && argument != "weather" => args.single::<String>().ok(),
+        _ => None,
+    };
+    let location = match ident_or_home(ctx, &source, location, &database_user).await? {
+        Some(location) => location,
+        None => return Ok(()),
+    };
+    let station_filter = match StationFilter::parse(args.rest()) {
+        Ok(station_filter) => station_filter,
+        Err(why) => { msg.channel_id.say(ctx, format!(":no_entry_sign: {}", why)).await?; return Ok(()); }
+    };
 
-    // Check if the bot can manage messages, And if so enable the bot to remove reactions.
-    let mut can_manage_messages = false;
-    if let Some(guild) = msg.guild(&ctx).await {
-        can_manage_messages = guild
-            .user_permissions_in(guild.channels.get(&msg.channel_id).unwrap(), guild.members.get(&ctx.http.get_current_user().await?.id).unwrap())
-            .unwrap().contains(Permissions::MANAGE_MESSAGES);
+    // Coordinates are used as is, Idents are looked up
+    let provider = get_provider(ctx).await;
+    let (latitude, longitude, origin) = match location.split_once(',') {
+        Some((latitude, longitude)) => match (latitude.trim().parse::<f32>(), longitude.trim().parse::<f32>()) {
+            // NaN and infinity parse too
+            (Ok(latitude), Ok(longitude)) if latitude.abs() <= 90.0 && longitude.abs() <= 180.0 => (latitude, longitude, None),
+            _ => { msg.channel_id.say(ctx, ":no_entry_sign: Couldn't parse the coordinates, Use lat,lon in decimal degrees within ±90 and ±180.").await?; return Ok(()); }
+        },
+        None => match resolve_station(ctx, &source, &location).await? {
+            Some(avwx_data) => (avwx_data.latitude, avwx_data.longitude, Some(avwx_data.icao)),
+            None => return Ok(()),
+        },
+    };
+
+    let stations = match provider.nearest(latitude, longitude, 100).await {
+        Ok(stations) => stations,
+        Err(why) => return send_provider_error(ctx, &source, why).await,
+    };
+
+    // Filter and sort on distance ourselves, Not every provider sorts
+    let mut matches = stations.into_iter()
+        .filter(|station| Some(&station.icao) != origin.as_ref() && station_filter.matches(station))
+        .map(|station| {
+            let route = great_circle((latitude as f64, longitude as f64), (station.latitude as f64, station.longitude as f64));
+            (station, route)
+        })
+        .collect::<Vec<_>>();
+    matches.sort_by(|a, b| a.1.distance_nm.total_cmp(&b.1.distance_nm));
+
+    if matches.is_empty() {
+        msg.channel_id.say(ctx, ":no_entry_sign: No stations nearby match those filters.").await?;
+        return Ok(());
     }
 
-    // Create a reaction collector and wait for someone to react to the message
-    let mut reactions_collector = sent_message.await_reactions(&ctx).timeout(Duration::from_secs(5 * 60))
-        .author_id(msg.author.id).added(true).removed(!can_manage_messages).await;
-    while let Some(reaction) = reactions_collector.next().await {
-        // Delete the reaction
-        if can_manage_messages {
-            reaction.as_inner_ref().delete(&ctx.http).await?;
+    let location_name = origin.unwrap_or_else(|| format!("{}, {}", latitude, longitude));
+    let pages = matches.chunks(NEAREST_PER_PAGE)
+        .map(|page_matches| create_nearest_embed(&location_name, page_matches, &database_user.units))
+        .collect::<Vec<CreateEmbed>>();
+
+    Paginator::new(pages).send(ctx, &source).await
+}
+
+#[command]
+#[max_args(1)]
+#[aliases("notams")]
+#[usage = "[ident]"]
+#[description = "Shows the active NOTAMs for an airport, Sorted into runway closures, Navaid outages, Obstacles and the rest"]
+async fn notam(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    // Parse the arguments
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let icao = match ident_or_home(ctx, &source, args.single::<String>().ok(), &database_user).await? {
+        Some(icao) => icao.to_uppercase(),
+        None => return Ok(()),
+    };
+
+    // Fetch the data
+    let notam_source = match get_notam_source(ctx).await {
+        Some(notam_source) => notam_source,
+        None => { msg.channel_id.say(ctx, ":no_entry_sign: There is no NOTAM source set up for this bot.").await?; return Ok(()); }
+    };
+    let mut notams = match notam_source.notams(&icao).await {
+        Ok(notams) => notams,
+        Err(why) => return send_provider_error(ctx, &source, why).await,
+    };
+
+    if notams.is_empty() {
+        msg.channel_id.say(ctx, format!(":information_source: There are no active NOTAMs for {}.", icao)).await?;
+        return Ok(());
+    }
+
+    // Build the pages, The summary first and then the NOTAMs of every category
+    notams.sort_by_key(|notam| notam.category);
+    let mut pages = vec![create_notam_summary_embed(&icao, &notams)];
+    for category in &[NotamCategory::RunwayClosure, NotamCategory::NavaidOutage, NotamCategory::Obstacle, NotamCategory::Other] {
+        let category_notams = notams.iter().filter(|notam| notam.category == *category).collect::<Vec<&Notam>>();
+        for page_notams in category_notams.chunks(NOTAMS_PER_PAGE) {
+            pages.push(create_notam_embed(&icao, *category, page_notams));
         }
+    }
+
+    Paginator::new(pages).send(ctx, &source).await
+}
 
-        let emoji = &reaction.as_inner_ref().emoji.to_string();
-        if emoji == "⬅" && current_pos != 0 {
-            current_pos -= 1;
-        } else if emoji == "➡" && &current_pos != &avwx_data.runways.len() {
-            current_pos += 1;
+#[command]
+#[aliases("densityaltitude", "density")]
+#[usage = "[ident] [elevation<ft|m>] [temperature<C|F>] [altimeter<inHg|hPa>]"]
+#[description = "Works out the pressure and density altitude at an airport, Giving the elevation, Temperature and altimeter works it out without any API"]
+async fn da(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    // Sort the arguments into the ident and values that override the fetched ones
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let (mut ident, mut elevation_ft, mut temperature_c, mut altimeter) = (None, None, None, None);
+    for argument in args.raw() {
+        if let Some(value) = parse_elevation(argument) {
+            elevation_ft = Some(value);
+        } else if let Some(value) = parse_temperature(argument) {
+            temperature_c = Some(value);
+        } else if let Some(value) = parse_altimeter(argument) {
+            altimeter = Some(value);
+        } else {
+            ident = Some(argument.to_string());
+        }
+    }
+
+    // Only fetch what wasn't given
+    let mut station = None;
+    if elevation_ft.is_none() || temperature_c.is_none() || altimeter.is_none() {
+        let mut icao = match ident_or_home(ctx, &source, ident, &database_user).await? {
+            Some(icao) => icao,
+            None => return Ok(()),
+        };
+        if elevation_ft.is_none() {
+            let avwx_data = match resolve_station(ctx, &source, &icao).await? {
+                Some(avwx_data) => avwx_data,
+                None => return Ok(()),
+            };
+            elevation_ft = Some(avwx_data.elevation_ft as f32);
+            icao = avwx_data.icao;
+        }
+        if temperature_c.is_none() || altimeter.is_none() {
+            let metar = match get_provider(ctx).await.metar(&icao).await {
+                Ok(metar) => metar,
+                Err(why) => return send_provider_error(ctx, &source, why).await,
+            };
+            temperature_c = temperature_c.or_else(|| metar.temperature.map(|temperature| temperature as f32));
+            altimeter = altimeter.or(metar.altimeter);
         }
+        station = Some(icao);
+    }
+
+    let (elevation_ft, temperature_c, altimeter) = match (elevation_ft, temperature_c, altimeter) {
+        (Some(elevation_ft), Some(temperature_c), Some(altimeter)) => (elevation_ft, temperature_c, altimeter),
+        _ => {
+            msg.channel_id.say(ctx, ":no_entry_sign: The METAR is missing the temperature or altimeter setting, Give them like `da EHAM 25C 1013`.").await?;
+            return Ok(());
+        }
+    };
+
+    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+        e.0 = create_density_altitude_embed(station.as_deref(), elevation_ft, temperature_c, altimeter, &database_user.units).0;
+        e
+    })).await?;
+
+    Ok(())
+}
+
+#[command]
+#[max_args(2)]
+#[aliases("sun", "sunrise", "sunset")]
+#[usage = "[ident] [YYYY-MM-DD]"]
+#[description = "Shows sunrise, Sunset and civil twilight at an airport in UTC and local time, Today when no date is given"]
+async fn daylight(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+    // Parse the arguments, A date first means the ident was left out
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+    let ident = match args.current() {
+        Some(argument) if NaiveDate::parse_from_str(argument, "%Y-%m-%d").is_err() => args.single::<String>().ok(),
+        _ => None,
+    };
+    let icao = match ident_or_home(ctx, &source, ident, &database_user).await? {
+        Some(icao) => icao,
+        None => return Ok(()),
+    };
+    let date = match args.current() {
+        Some(argument) => match NaiveDate::parse_from_str(argument, "%Y-%m-%d") {
+            Ok(date) => Some(date),
+            Err(_) => { msg.channel_id.say(ctx, ":no_entry_sign: The date has to look like 2021-06-21.").await?; return Ok(()); }
+        },
+        None => None,
+    };
 
-        // Edit the original message
-        &sent_message.edit(&ctx.http, |f| f.embed(|embed| {
-            if current_pos == 0 {
-                embed.0 = create_icao_embed(current_pos as i32, &avwx_data).0;
-            } else {
-                embed.0 = create_icao_runway_embed(current_pos as i32, &avwx_data.runways.get(current_pos - 1).unwrap()).0;
+    // Fetch the data
+    let avwx_data = match resolve_station(ctx, &source, &icao).await? {
+        Some(avwx_data) => avwx_data,
+        None => return Ok(()),
+    };
+
+    // Today is the date at the airport, Not in UTC
+    let time_zone = local_time_zone(avwx_data.latitude as f64, avwx_data.longitude as f64);
+    let date = date.unwrap_or_else(|| match time_zone {
+        Some(time_zone) => Utc::now().with_timezone(&time_zone).date_naive(),
+        None => Utc::now().date_naive(),
+    });
+
+    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+        e.0 = create_daylight_embed(&avwx_data, date, time_zone).0;
+        e
+    })).await?;
+
+    Ok(())
+}
+
+// The ident from the arguments, Or the user's home airport when it's left out.
+async fn ident_or_home(ctx: &Context, source: &CommandSource<'_>, ident: Option<String>, database_user: &DatabaseUser) -> Result<Option<String>, CommandError> {
+    if let Some(ident) = ident.or_else(|| database_user.home_airport.clone()) {
+        return Ok(Some(ident));
+    }
+
+    source.say(ctx, ":no_entry_sign: Give an ICAO ident or set a home airport with `settings home <ident>`.").await?;
+    Ok(None)
+}
+
+// Looks the station up in the local airport database first, And asks the provider when it isn't there.
+//
+// Errors are sent to the user and an ambiguous query lets them pick, None means the command should stop.
+pub(crate) async fn resolve_station(ctx: &Context, source: &CommandSource<'_>, query: &str) -> Result<Option<AvwxIcao>, CommandError> {
+    let candidates = {
+        let airport_database = ctx.data.read().await.get::<AirportDatabaseContainer>().cloned().unwrap();
+        let airport_database = airport_database.read().await;
+        airport_database.search(query).into_iter()
+            .map(|(station, score)| (station.clone(), score))
+            .collect::<Vec<(AvwxIcao, f64)>>()
+    };
+
+    match candidates.as_slice() {
+        [] => match get_provider(ctx).await.station(query).await {
+            Ok(station) => Ok(Some(station)),
+            Err(why) => {
+                send_provider_error(ctx, source, why).await?;
+                Ok(None)
             }
-            embed
-        })).await;
+        },
+        [(station, _)] => Ok(Some(station.clone())),
+        [(best, best_score), (_, second_score), ..] if best_score - second_score >= AMBIGUOUS_SCORE_MARGIN => Ok(Some(best.clone())),
+        _ => pick_station(ctx, source, query, candidates.into_iter().map(|(station, _)| station).collect()).await,
     }
+}
 
-    // Delete all reactions once done
-    if can_manage_messages {
-        &sent_message.delete_reactions(&ctx.http).await?;
+// Lets the author pick one of the stations by reacting with its number or typing it.
+async fn pick_station(ctx: &Context, source: &CommandSource<'_>, query: &str, stations: Vec<AvwxIcao>) -> Result<Option<AvwxIcao>, CommandError> {
+    let stations_text = stations.iter().zip(NUMBER_EMOJIS.iter())
+        .map(|(station, number)| format!("{} **{}** - {} ({}, {})", number, station.icao, station.name, station.city, station.country))
+        .collect::<Vec<String>>().join("\n");
+    let mut embed = CreateEmbed::default();
+    embed.title(format!("Which airport did you mean with \"{}\"?", query))
+        .description(stations_text)
+        .footer(|f| f.text("React with its number or type it."))
+        .color(Colour::BLITZ_BLUE);
+    let sent_message = source.send_embed(ctx, embed).await?;
+    for number in NUMBER_EMOJIS.iter().take(stations.len()) {
+        sent_message.react(&ctx.http, ReactionType::Unicode(number.to_string())).await?;
+    }
+
+    // Whichever comes first, A reaction or a reply
+    let picked = tokio::select! {
+        reaction = sent_message.await_reaction(&ctx).author_id(source.author().id).timeout(PICK_TIMEOUT) => {
+            reaction.and_then(|reaction| {
+                let emoji = reaction.as_inner_ref().emoji.to_string();
+                NUMBER_EMOJIS.iter().position(|number| *number == emoji)
+            })
+        }
+        reply = source.author().await_reply(&ctx).channel_id(source.channel_id()).timeout(PICK_TIMEOUT) => {
+            reply.and_then(|reply| reply.content.trim().parse::<usize>().ok()).and_then(|number| number.checked_sub(1))
+        }
+    };
+    source.delete(ctx, &sent_message).await?;
+
+    match picked.and_then(|index| stations.into_iter().nth(index)) {
+        Some(station) => Ok(Some(station)),
+        None => {
+            source.say(ctx, ":no_entry_sign: No airport was picked.").await?;
+            Ok(None)
+        }
+    }
+}
+
+// Gets the NOTAM source of this deployment, If there is one.
+async fn get_notam_source(ctx: &Context) -> Option<Arc<dyn NotamSource>> {
+    ctx.data.read().await.get::<NotamSourceContainer>().cloned().unwrap()
+}
+
+// Gets the aviation data provider of this deployment.
+async fn get_provider(ctx: &Context) -> Arc<dyn AviationProvider> {
+    ctx.data.read().await.get::<AviationProviderContainer>().cloned().unwrap()
+}
+
+// Tells the user why the provider couldn't answer.
+async fn send_provider_error(ctx: &Context, source: &CommandSource<'_>, why: ProviderError) -> CommandResult {
+    match why {
+        ProviderError::Decode(why) => {
+            let mut embed = CreateEmbed::default();
+            embed.title("An error occurred decoding the response.")
+                .description(format!("```{}```", why))
+                .color(Colour::RED);
+            source.send_embed(ctx, embed).await?;
+        }
+        ProviderError::NotFound(why) => { source.say(ctx, format!(":no_entry_sign: {}", why)).await?; }
+        ProviderError::RateLimited(provider) => {
+            source.say(ctx, format!(":no_entry_sign: {} is rate limiting the bot, Try again later.", provider)).await?;
+        }
+        ProviderError::Request(_) => { source.say(ctx, ":no_entry_sign: An error occurred fetching the data.").await?; }
+        ProviderError::Unsupported(why) => { source.say(ctx, format!(":no_entry_sign: {}.", why)).await?; }
     }
 
     Ok(())
 }
 
-fn create_icao_embed(position: i32, avwx_icao: &AvwxIcao) -> CreateEmbed {
+fn create_icao_embed(avwx_icao: &AvwxIcao, notams: &[Notam], units: &UserUnits) -> CreateEmbed {
     let mut embed = CreateEmbed::default();
 
     embed.title(format!("{} - {}", avwx_icao.icao, &avwx_icao.name))
         .url(&avwx_icao.wiki)
         .field("Location", format!("Country: {}\nCity: {}\nLatitude: {}\nLongitude: {}",
                                    &avwx_icao.country, &avwx_icao.city, &avwx_icao.latitude, &avwx_icao.longitude), true)
-        .field("Elevation", format!("In feet: {}\nIn meters: {}",
-                                    &avwx_icao.elevation_ft, &avwx_icao.elevation_m), true)
+        .field("Elevation", units.format_altitude(avwx_icao.elevation_ft as f32), true)
         .field("Other", format!("Iata: {}\nType: {}\nReporting: {}", &avwx_icao.iata, &avwx_icao.airport_type, &avwx_icao.reporting), true)
-        .footer(|f| f.text(format!("Menu position: {}", position)))
         .color(Colour::BLITZ_BLUE);
 
     let mut extra_text = String::new();
@@ -120,19 +632,427 @@ fn create_icao_embed(position: i32, avwx_icao: &AvwxIcao) -> CreateEmbed {
     if let Some(note) = &avwx_icao.note { extra_text += &format!("Note: {}\n", note) }
     if !extra_text.is_empty() { embed.field("Extra", extra_text, true); }
 
+    let closed_runways = avwx_icao.runways.iter()
+        .filter(|runway| is_runway_closed(runway, notams))
+        .map(|runway| format!("{}-{}", runway.ident1, runway.ident2))
+        .collect::<Vec<String>>();
+    if !closed_runways.is_empty() { embed.field("Closed runways", closed_runways.join("\n"), true); }
+
+    // In UTC only, The daylight command also has the local times
+    let daylight = daylight_times(avwx_icao.latitude as f64, avwx_icao.longitude as f64, Utc::now().date_naive());
+    embed.field("Daylight today", format_daylight(&daylight, |time| time.format("%H:%MZ").to_string()), true);
+
     embed
 }
 
-fn create_icao_runway_embed(position: i32, avwx_icao_runway: &AvwxIcaoRunway) -> CreateEmbed {
+fn create_icao_runway_embed(avwx_icao_runway: &AvwxIcaoRunway, closed: bool, units: &UserUnits) -> CreateEmbed {
     let mut embed = CreateEmbed::default();
 
-    embed.title(format!("Runway {}-{}", &avwx_icao_runway.ident1, &avwx_icao_runway.ident2))
+    embed.title(format!("Runway {}-{}{}", &avwx_icao_runway.ident1, &avwx_icao_runway.ident2, if closed { " (Closed by NOTAM)" } else { "" }))
         .field("Bearings", format!("One: {}\nTwo: {}", &avwx_icao_runway.bearing1, &avwx_icao_runway.bearing2), true)
         //.field("Idents", format!("One: {}\nTwo: {}", &avwx_icao_runway.ident1, &avwx_icao_runway.ident2), true)
-        .field("Size", format!("Width: {}\nLenght: {}", &avwx_icao_runway.width_ft, &avwx_icao_runway.width_ft), true)
+        .field("Size", format!("Length: {}\nWidth: {}",
+                               units.format_altitude(avwx_icao_runway.length_ft as f32), units.format_altitude(avwx_icao_runway.width_ft as f32)), true)
         .field("Other", format!("Surface: {}\nLights: {}", &avwx_icao_runway.surface, &avwx_icao_runway.lights), true)
-        .footer(|f| f.text(format!("Menu position: {}", position)))
+        .color(if closed { Colour::RED } else { Colour::BLITZ_BLUE });
+
+    embed
+}
+
+fn create_icao_frequencies_embed(icao: &str, frequencies: &[OurAirportsFrequency]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("{} - Frequencies", icao))
+        .color(Colour::BLITZ_BLUE);
+
+    // Embeds can only have 25 fields
+    for frequency in frequencies.iter().take(25) {
+        embed.field(&frequency.frequency_type, format!("{:.3} MHz\n{}", frequency.frequency_mhz, &frequency.description), true);
+    }
+
+    embed
+}
+
+fn create_icao_navaids_embed(icao: &str, navaids: &[(&OurAirportsNavaid, GreatCircle)]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("{} - Navaids", icao))
+        .color(Colour::BLITZ_BLUE);
+
+    for (navaid, route) in navaids {
+        embed.field(format!("{} {}", &navaid.ident, &navaid.navaid_type),
+                    format!("{}\n{}\n{:.1} NM at {:03.0}°", &navaid.name, navaid.format_frequency(), route.distance_nm, route.initial_bearing), true);
+    }
+
+    embed
+}
+
+fn create_icao_charts_embed(avwx_icao: &AvwxIcao) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let links_text = chart_links(&avwx_icao.icao, &avwx_icao.country).iter()
+        .map(|(name, link)| format!("[{}]({})", name, link))
+        .collect::<Vec<String>>().join("\n");
+    embed.title(format!("{} - Charts", avwx_icao.icao))
+        .description(links_text)
+        .color(Colour::BLITZ_BLUE);
+
+    embed
+}
+
+fn create_density_altitude_embed(icao: Option<&str>, elevation_ft: f32, temperature_c: f32, altimeter: Altimeter, units: &UserUnits) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let pressure_altitude_ft = pressure_altitude(elevation_ft, altimeter);
+    let density_altitude_ft = density_altitude(pressure_altitude_ft, temperature_c);
+    let band = DensityAltitudeBand::from_density_altitude(density_altitude_ft);
+
+    let title = match icao {
+        Some(icao) => format!("Density altitude at {}", icao),
+        None => String::from("Density altitude"),
+    };
+    embed.title(title)
+        .description(band.advice())
+        .field("Elevation", units.format_altitude(elevation_ft), true)
+        .field("Temperature", format!("Outside: {}\nISA: {}",
+                                      units.format_temperature(temperature_c), units.format_temperature(isa_temperature(pressure_altitude_ft))), true)
+        .field("Altimeter", units.format_pressure(altimeter), true)
+        .field("Pressure altitude", units.format_altitude(pressure_altitude_ft), true)
+        .field("Density altitude", units.format_altitude(density_altitude_ft), true)
+        .footer(|f| f.text(match icao {
+            Some(_) => "Anything not given comes from the station and its METAR",
+            None => "Worked out from the given values",
+        }))
+        .color(match band {
+            DensityAltitudeBand::Normal => Colour::from_rgb(0, 166, 81),
+            DensityAltitudeBand::Caution => Colour::GOLD,
+            DensityAltitudeBand::Warning => Colour::ORANGE,
+            DensityAltitudeBand::Danger => Colour::RED,
+        });
+
+    embed
+}
+
+fn create_daylight_embed(avwx_icao: &AvwxIcao, date: NaiveDate, time_zone: Option<Tz>) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let daylight = daylight_times(avwx_icao.latitude as f64, avwx_icao.longitude as f64, date);
+    embed.title(format!("{} - Daylight on {}", avwx_icao.icao, date.format("%Y-%m-%d")))
+        .url(&avwx_icao.wiki)
+        .field("UTC", format_daylight(&daylight, |time| format_daylight_time(time, date, &Utc, "Z")), true)
+        .footer(|f| f.text("Civil twilight is when the sun is less than 6° below the horizon"))
+        .color(Colour::GOLD);
+
+    // Offshore stations don't have a time zone
+    if let Some(time_zone) = time_zone {
+        embed.field(format!("Local ({})", time_zone.name()),
+                    format_daylight(&daylight, |time| format_daylight_time(time, date, &time_zone, "")), true);
+    }
+
+    embed
+}
+
+fn create_winds_embed(avwx_icao: &AvwxIcao, wind: &Wind, wind_direction: u16, crosswind_limit: Option<f32>, units: &UserUnits) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    // Components for both ends of every runway, Steady and with the gusts
+    let wind_speed = wind.speed_in_knots();
+    let runway_ends = avwx_icao.runways.iter()
+        .flat_map(|runway| vec![(&runway.ident1, runway.bearing1), (&runway.ident2, runway.bearing2)])
+        .map(|(ident, bearing)| (ident, wind_components(bearing, wind_direction as f32, wind_speed),
+                                 wind.gust_in_knots().map(|gust| wind_components(bearing, wind_direction as f32, gust))))
+        .collect::<Vec<_>>();
+
+    // The favoured runway end has the most headwind
+    let favoured = runway_ends.iter().enumerate()
+        .max_by(|(_, a), (_, b)| a.1.headwind.total_cmp(&b.1.headwind))
+        .map(|(index, _)| index);
+
+    embed.title(format!("Runway winds for {} - {}", avwx_icao.icao, &avwx_icao.name))
+        .description(format!("Wind: {}", format_wind(&Some(*wind), units).replace('\n', ", ")))
+        .color(Colour::BLITZ_BLUE);
+    if let Some(crosswind_limit) = crosswind_limit {
+        embed.footer(|f| f.text(format!("Crosswind limit: {}", units.format_speed(crosswind_limit))));
+    }
+
+    for (index, (ident, steady, gusting)) in runway_ends.iter().enumerate() {
+        let mut runway_text = if steady.headwind >= 0.0 {
+            format!("Headwind: {}", units.format_speed(steady.headwind))
+        } else {
+            format!("Tailwind: {}", units.format_speed(-steady.headwind))
+        };
+        runway_text += &format!("\nCrosswind: {} from the {}", units.format_speed(steady.crosswind.abs()), if steady.crosswind < 0.0 { "left" } else { "right" });
+        if let Some(gusting) = gusting {
+            runway_text += &format!("\nIn gusts: {}", units.format_speed(gusting.crosswind.abs()));
+        }
+
+        // Gusts count against the limit
+        let highest_crosswind = gusting.unwrap_or(*steady).crosswind.abs();
+        if crosswind_limit.map(|crosswind_limit| highest_crosswind > crosswind_limit).unwrap_or(false) {
+            runway_text += "\n:warning: Over the crosswind limit";
+        }
+
+        if favoured == Some(index) {
+            embed.field(format!("Runway {} :star:", ident), runway_text, true);
+        } else {
+            embed.field(format!("Runway {}", ident), runway_text, true);
+        }
+    }
+
+    embed
+}
+
+fn create_route_embed(from: &AvwxIcao, to: &AvwxIcao, groundspeed: Option<f64>, from_metar: &Option<Metar>, to_metar: &Option<Metar>, units: &UserUnits) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let route = great_circle((from.latitude as f64, from.longitude as f64), (to.latitude as f64, to.longitude as f64));
+
+    embed.title(format!("{} to {}", from.icao, to.icao))
+        .description(format!("{} to {}", &from.name, &to.name))
+        .field("Distance", format!("{:.0} NM\n{:.0} km", route.distance_nm, route.distance_nm * KILOMETERS_PER_NAUTICAL_MILE), true)
+        .field("True bearing", format!("Initial: {:03.0}°\nFinal: {:03.0}°", route.initial_bearing, route.final_bearing), true)
+        .color(Colour::BLITZ_BLUE);
+
+    if let Some(groundspeed) = groundspeed {
+        embed.field("Enroute time", format!("{} at {}", format_enroute_time(route.distance_nm, groundspeed), units.format_speed(groundspeed as f32)), true);
+    }
+    for (station, metar) in [(from, from_metar), (to, to_metar)] {
+        if let Some(metar) = metar {
+            embed.field(format!("{} METAR - {}", station.icao, metar.flight_rules), format!("```{}```", &metar.raw), false);
+        }
+    }
+
+    embed
+}
+
+fn create_nearest_embed(location_name: &str, page_matches: &[(AvwxIcao, GreatCircle)], units: &UserUnits) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("Nearest stations to {}", location_name))
+        .color(Colour::BLITZ_BLUE);
+
+    for (station, route) in page_matches {
+        let mut station_text = format!("{:.0} NM ({:.0} km) at {:03.0}°\nType: {}\nReporting: {}",
+                                       route.distance_nm, route.distance_nm * KILOMETERS_PER_NAUTICAL_MILE, route.initial_bearing,
+                                       &station.airport_type, &station.reporting);
+        if let Some(runway) = station.runways.iter().max_by_key(|runway| runway.length_ft) {
+            station_text += &format!("\nLongest runway: {} {}", units.format_altitude(runway.length_ft as f32), &runway.surface);
+        }
+
+        embed.field(format!("{} - {}", station.icao, &station.name), station_text, false);
+    }
+
+    embed
+}
+
+fn create_metar_embed(metar: &Metar, units: &UserUnits) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("METAR for {}", &metar.station))
+        .description(format!("```{}```", &metar.raw))
+        .field("Wind", format_wind(&metar.conditions.wind, units), true)
+        .field("Visibility", format_visibility(&metar.conditions), true)
+        .field("Clouds", format_clouds(&metar.conditions, units), true)
+        .field("Temperature", format!("Temperature: {}\nDewpoint: {}",
+                                      format_temperature(metar.temperature, units), format_temperature(metar.dewpoint, units)), true)
+        .field("Altimeter", format_altimeter(metar.altimeter, units), true)
+        .field("Flight rules", metar.flight_rules, true)
+        .color(flight_rules_colour(metar.flight_rules));
+
+    if !metar.conditions.weather.is_empty() {
+        embed.field("Weather", format_weather(&metar.conditions.weather), true);
+    }
+    if let Some(time) = metar.time {
+        embed.footer(|f| f.text(format!("Observed at {}", time)));
+    }
+
+    embed
+}
+
+fn create_taf_embed(taf: &Taf) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("TAF for {}", &taf.station))
+        .description(format!("```{}```", &taf.raw))
+        .field("Periods", taf.periods.len(), true)
         .color(Colour::BLITZ_BLUE);
 
+    if let Some(valid) = taf.valid {
+        embed.field("Valid", format!("From: {}\nTo: {}", valid.from, valid.to), true);
+    }
+    if let Some(time) = taf.time {
+        embed.field("Issued", time, true);
+    }
+
+    embed
+}
+
+fn create_taf_period_embed(taf: &Taf, period: &TafPeriod, units: &UserUnits) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("{} - {}", &taf.station, period.kind.name()))
+        .description(format!("```{}```", &period.raw))
+        .field("Wind", format_wind(&period.conditions.wind, units), true)
+        .field("Visibility", format_visibility(&period.conditions), true)
+        .field("Clouds", format_clouds(&period.conditions, units), true)
+        .field("Flight rules", period.flight_rules, true)
+        .color(flight_rules_colour(period.flight_rules));
+
+    if let Some(valid) = period.valid {
+        embed.field("Valid", format!("From: {}\nTo: {}", valid.from, valid.to), true);
+    }
+    if !period.conditions.weather.is_empty() {
+        embed.field("Weather", format_weather(&period.conditions.weather), true);
+    }
+
+    embed
+}
+
+fn create_notam_summary_embed(icao: &str, notams: &[Notam]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("NOTAMs for {}", icao))
+        .description(format!("{} active NOTAMs", notams.len()))
+        .color(Colour::BLITZ_BLUE);
+
+    for category in &[NotamCategory::RunwayClosure, NotamCategory::NavaidOutage, NotamCategory::Obstacle, NotamCategory::Other] {
+        let count = notams.iter().filter(|notam| notam.category == *category).count();
+        embed.field(category, count, true);
+    }
+
+    let closed_runways = notams.iter().flat_map(|notam| notam.closed_runways()).collect::<Vec<String>>();
+    if !closed_runways.is_empty() { embed.field("Closed runways", closed_runways.join(", "), false); }
+
+    embed
+}
+
+fn create_notam_embed(icao: &str, category: NotamCategory, notams: &[&Notam]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed.title(format!("{} - {}", icao, category))
+        .color(Colour::BLITZ_BLUE);
+
+    for notam in notams {
+        // Fields can only hold 1024 characters
+        let mut notam_text = notam.text.chars().take(900).collect::<String>();
+        if notam_text.len() < notam.text.len() { notam_text += "..." }
+        if let (Some(start), Some(end)) = (&notam.effective_start, &notam.effective_end) {
+            notam_text += &format!("\n*{} until {}*", start, end);
+        }
+        embed.field(&notam.id, notam_text, false);
+    }
+
     embed
-}
\ No newline at end of file
+}
+
+// One line per sun event, The times are formatted by the caller
+fn format_daylight(daylight: &DaylightTimes, format_time: impl Fn(DateTime<Utc>) -> String) -> String {
+    let crossing_lines = |crossing: SunCrossing, rise_name: &str, set_name: &str, above: &str, below: &str| match crossing {
+        SunCrossing::Times(rise, set) => (format!("{}: {}", rise_name, format_time(rise)), format!("{}: {}", set_name, format_time(set))),
+        SunCrossing::AlwaysAbove => (format!("{}: {}", rise_name, above), format!("{}: {}", set_name, above)),
+        SunCrossing::AlwaysBelow => (format!("{}: {}", rise_name, below), format!("{}: {}", set_name, below)),
+    };
+    let (civil_dawn, civil_dusk) = crossing_lines(daylight.civil_twilight, "Civil dawn", "Civil dusk", "No night", "Night all day");
+    let (sunrise, sunset) = crossing_lines(daylight.sun, "Sunrise", "Sunset", "Sun up all day", "Sun down all day");
+
+    format!("{}\n{}\nSolar noon: {}\n{}\n{}", civil_dawn, sunrise, format_time(daylight.solar_noon), sunset, civil_dusk)
+}
+
+// Like 05:18, With the date when it's on another day than asked for
+fn format_daylight_time<T: TimeZone>(time: DateTime<Utc>, date: NaiveDate, time_zone: &T, suffix: &str) -> String where T::Offset: fmt::Display {
+    let local_time = time.with_timezone(time_zone);
+    if local_time.date_naive() == date {
+        format!("{}{}", local_time.format("%H:%M"), suffix)
+    } else {
+        format!("{}{} ({})", local_time.format("%H:%M"), suffix, local_time.format("%b %d"))
+    }
+}
+
+fn format_wind(wind: &Option<Wind>, units: &UserUnits) -> String {
+    let wind = match wind {
+        Some(wind) => wind,
+        None => return String::from("Unknown"),
+    };
+
+    let mut wind_text = if wind.speed == 0 {
+        String::from("Calm")
+    } else {
+        match wind.direction {
+            Some(direction) => format!("{:03}° at {}", direction, units.format_speed(wind.speed_in_knots())),
+            None => format!("Variable at {}", units.format_speed(wind.speed_in_knots())),
+        }
+    };
+    if let Some(gust) = wind.gust_in_knots() {
+        wind_text += &format!("\nGusting {}", units.format_speed(gust));
+    }
+    if let Some((from, to)) = wind.variable_from {
+        wind_text += &format!("\nVarying {:03}° to {:03}°", from, to);
+    }
+
+    wind_text
+}
+
+fn format_visibility(conditions: &Conditions) -> String {
+    if conditions.cavok {
+        return String::from("CAVOK");
+    }
+    let visibility = match conditions.visibility {
+        Some(visibility) => visibility,
+        None => return String::from("Unknown"),
+    };
+
+    let value = match visibility.unit {
+        DistanceUnit::Meters if visibility.value >= 9999.0 => String::from("10 km"),
+        DistanceUnit::Meters => format!("{} m", visibility.value),
+        DistanceUnit::StatuteMiles => format!("{} SM", visibility.value),
+    };
+    match visibility.modifier {
+        Some(VisibilityModifier::LessThan) => format!("Less than {}", value),
+        Some(VisibilityModifier::GreaterThan) => format!("{} or more", value),
+        None => value,
+    }
+}
+
+fn format_clouds(conditions: &Conditions, units: &UserUnits) -> String {
+    if conditions.cavok {
+        return String::from("CAVOK");
+    } else if conditions.clouds.is_empty() {
+        return String::from("Clear");
+    }
+
+    conditions.clouds.iter().map(|cloud| {
+        let mut cloud_text = match cloud.altitude {
+            Some(altitude) => format!("{} at {}", cloud.coverage.abbreviation(), units.format_altitude(altitude as f32)),
+            None => cloud.coverage.abbreviation().to_string(),
+        };
+        if let Some(modifier) = &cloud.modifier { cloud_text += &format!(" ({})", modifier) }
+        cloud_text
+    }).collect::<Vec<String>>().join("\n")
+}
+
+fn format_temperature(temperature: Option<i32>, units: &UserUnits) -> String {
+    match temperature {
+        Some(temperature) => units.format_temperature(temperature as f32),
+        None => String::from("Unknown"),
+    }
+}
+
+fn format_altimeter(altimeter: Option<Altimeter>, units: &UserUnits) -> String {
+    match altimeter {
+        Some(altimeter) => units.format_pressure(altimeter),
+        None => String::from("Unknown"),
+    }
+}
+
+fn format_weather(weather: &[String]) -> String {
+    weather.iter().map(|code| describe_weather(code)).collect::<Vec<String>>().join("\n")
+}
+
+fn flight_rules_colour(flight_rules: FlightRules) -> Colour {
+    match flight_rules {
+        FlightRules::Vfr => Colour::from_rgb(0, 166, 81),
+        FlightRules::Mvfr => Colour::from_rgb(0, 84, 166),
+        FlightRules::Ifr => Colour::from_rgb(237, 28, 36),
+        FlightRules::Lifr => Colour::from_rgb(191, 0, 191),
+    }
+}
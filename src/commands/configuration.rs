@@ -2,9 +2,13 @@ use serenity::prelude::*;
 use serenity::model::prelude::*;
 use serenity::framework::standard::{CommandResult, macros::command, Args};
 use serenity::utils::Colour;
-use crate::helpers::database_helper::{DatabaseGuild, GuildCounting};
+use crate::helpers::database_helper::{DatabaseGuild, DatabaseCountingStats, GuildCounting, WeatherAlert};
 use serenity::builder::CreateEmbed;
-use crate::helpers::global_data::{CountingCache, PrefixCache};
+use crate::helpers::global_data::{CountingCache, PrefixCache, WeatherAlertCache, AviationProviderContainer};
+use crate::helpers::weather_alerts::AlertConditions;
+use crate::helpers::command_source::CommandSource;
+use crate::helpers::counting::{CountingFailAction, pick_counting_channel};
+use crate::helpers::counting_modes::CountingMode;
 
 #[command]
 #[description = "Sets the prefix for this server"]
@@ -13,36 +17,44 @@ use crate::helpers::global_data::{CountingCache, PrefixCache};
 #[only_in("guilds")]
 #[usage = "new_prefix"]
 async fn prefix(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+
     // Reset the prefix
     if args.is_empty() {
-        // Remove from cache
-        ctx.data.read().await.get::<PrefixCache>().unwrap().remove(&msg.guild_id.unwrap());
-
-        // Remove from database
-        let mut database_guild = DatabaseGuild::get_or_insert_new(ctx, msg.guild_id.unwrap().0 as i64).await;
-        database_guild.prefix = None;
-
-        DatabaseGuild::insert_or_replace(ctx, database_guild).await;
-
-        msg.channel_id.say(ctx, ":white_check_mark: Reset the prefix to the default value.").await?;
+        run_prefix(ctx, &source, None).await?;
     } else {
         let new_prefix = args.single::<String>();
         match new_prefix {
             Err(_) => {
-                msg.channel_id.say(ctx, ":no_entry_sign: The prefix provided was not valid.").await?;
+                source.say(ctx, ":no_entry_sign: The prefix provided was not valid.").await?;
             },
-            Ok(new_prefix) => {
-                // Put it in the cache
-                ctx.data.read().await.get::<PrefixCache>().unwrap().insert(msg.guild_id.unwrap(), new_prefix.clone());
+            Ok(new_prefix) => run_prefix(ctx, &source, Some(new_prefix)).await?,
+        }
+    }
 
-                // Put it in the database
-                let mut database_guild = DatabaseGuild::get_or_insert_new(ctx, msg.guild_id.unwrap().0 as i64).await;
-                database_guild.prefix = Some(new_prefix.clone());
+    Ok(())
+}
+
+pub(crate) async fn run_prefix(ctx: &Context, source: &CommandSource<'_>, new_prefix: Option<String>) -> CommandResult {
+    let guild_id = source.guild_id().unwrap();
+    match new_prefix {
+        None => {
+            // Remove from cache
+            ctx.data.read().await.get::<PrefixCache>().unwrap().remove(&guild_id);
 
-                DatabaseGuild::insert_or_replace(ctx, database_guild).await;
+            // Remove from database
+            DatabaseGuild::set_prefix(ctx, guild_id.0 as i64, None).await;
 
-                msg.channel_id.say(ctx, format!(":white_check_mark: Set the prefix to {}", new_prefix)).await?;
-            }
+            source.say(ctx, ":white_check_mark: Reset the prefix to the default value.").await?;
+        },
+        Some(new_prefix) => {
+            // Put it in the cache
+            ctx.data.read().await.get::<PrefixCache>().unwrap().insert(guild_id, new_prefix.clone());
+
+            // Put it in the database
+            DatabaseGuild::set_prefix(ctx, guild_id.0 as i64, Some(new_prefix.clone())).await;
+
+            source.say(ctx, format!(":white_check_mark: Set the prefix to {}", new_prefix)).await?;
         }
     }
 
@@ -50,72 +62,294 @@ async fn prefix(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
 }
 
 #[command]
-#[description = "Sets the counting channel"]
+#[description = "Adds, Removes or lists the counting channels and sets their rules"]
 #[required_permissions("ADMINISTRATOR")]
-#[max_args(1)]
+#[max_args(3)]
 #[only_in("guilds")]
 #[aliases("counting")]
-#[usage = "channel_id"]
+#[usage = "list | add channel | remove channel | consecutive on|off [channel] | fail delete|reset [channel] | mode decimal|math|hex|binary|roman [channel]"]
+#[example = "add #counting"]
+#[example = "fail reset #counting"]
+#[example = "mode roman"]
 async fn count(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+
+    // List the counting channels
     if args.is_empty() {
-        // Remove from database and cache
-        let mut database_guild = DatabaseGuild::get_or_insert_new(ctx, msg.guild_id.unwrap().0 as i64).await;
+        return list_counting_channels(ctx, &source).await;
+    }
 
-        // Check if there even is a counting channel
-        if database_guild.counting.is_none() {
-            msg.channel_id.say(ctx, ":no_entry_sign: There is no counting channel to remove.").await?;
-        } else {
-            // Remove from cache
-            ctx.data.read().await.get::<CountingCache>().unwrap().remove(&ChannelId::from(database_guild.counting.unwrap().channel as u64));
+    let action = args.single::<String>()?.to_lowercase();
+    match action.as_str() {
+        "list" => list_counting_channels(ctx, &source).await,
+        "add" | "remove" => {
+            let channel = match args.single::<ChannelId>() {
+                Ok(channel) => channel,
+                Err(why) => { source.send_embed(ctx, invalid_channel_embed(why.to_string())).await?; return Ok(()); }
+            };
 
-            //Remove from database
-            database_guild.counting = None;
-            DatabaseGuild::insert_or_replace(ctx, database_guild).await;
+            if action == "add" {
+                add_counting_channel(ctx, &source, channel).await
+            } else {
+                remove_counting_channel(ctx, &source, channel).await
+            }
+        }
+        "consecutive" | "fail" | "mode" => {
+            let value = args.single::<String>().unwrap_or_default();
+            let channel = if args.is_empty() {
+                None
+            } else {
+                match args.single::<ChannelId>() {
+                    Ok(channel) => Some(channel),
+                    Err(why) => { source.send_embed(ctx, invalid_channel_embed(why.to_string())).await?; return Ok(()); }
+                }
+            };
 
-            msg.channel_id.say(ctx, ":white_check_mark: Removed the counting channel.").await?;
+            match action.as_str() {
+                "consecutive" => {
+                    let allow_consecutive = match value.to_lowercase().as_str() {
+                        "on" | "yes" | "allow" => true,
+                        "off" | "no" | "disallow" => false,
+                        _ => { source.say(ctx, ":no_entry_sign: Use `on` or `off` for counting twice in a row.").await?; return Ok(()); }
+                    };
+                    update_counting_settings(ctx, &source, channel, Some(allow_consecutive), None, None).await
+                }
+                "fail" => {
+                    let on_fail = match CountingFailAction::parse(&value) {
+                        Some(on_fail) => on_fail,
+                        None => { source.say(ctx, ":no_entry_sign: Use `delete` or `reset` for what happens on a wrong count.").await?; return Ok(()); }
+                    };
+                    update_counting_settings(ctx, &source, channel, None, Some(on_fail), None).await
+                }
+                _ => {
+                    let mode = match CountingMode::parse(&value) {
+                        Some(mode) => mode,
+                        None => { source.say(ctx, ":no_entry_sign: The counting modes are `decimal`, `math`, `hex`, `binary` and `roman`, Which is decimal past 3999.").await?; return Ok(()); }
+                    };
+                    update_counting_settings(ctx, &source, channel, None, None, Some(mode)).await
+                }
+            }
         }
+        // A channel on its own adds it, Like setting the counting channel used to
+        _ => match action.parse::<ChannelId>() {
+            Ok(channel) => add_counting_channel(ctx, &source, channel).await,
+            Err(_) => { source.say(ctx, ":no_entry_sign: Use `list`, `add`, `remove`, `consecutive`, `fail` or `mode`, The rules go to this channel or the only counting channel when it's left out.").await?; Ok(()) }
+        },
+    }
+}
+
+pub(crate) async fn list_counting_channels(ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
+    let database_guild = DatabaseGuild::get_or_insert_new(ctx, source.guild_id().unwrap().0 as i64).await;
+
+    if database_guild.counting.is_empty() {
+        source.say(ctx, ":information_source: There are no counting channels in this server.").await?;
     } else {
-        let new_channel = args.single::<ChannelId>();
-        match new_channel {
-            Err(why) => {
-                msg.channel_id.send_message(&ctx.http, |m| m.embed(|embed| {
-                    embed.0 = invalid_channel_embed(why.to_string()).0;
-                    embed
-                })).await?;
-            },
-            Ok(new_channel) => {
-                let guild = msg.guild(&ctx).await.unwrap();
-                if !guild.channels.contains_key(&new_channel) {
-                    msg.channel_id.send_message(&ctx.http, |m| m.embed(|embed| {
-                        embed.0 = invalid_channel_embed(String::from("Channel not found in this guild.")).0;
-                        embed
-                    })).await?;
-                    return Ok(());
-                } else if !guild.user_permissions_in(guild.channels.get(&new_channel).unwrap(), guild.members.get(&ctx.http.get_current_user().await?.id).unwrap())
-                    .unwrap().contains(Permissions::MANAGE_MESSAGES) {
-                    msg.channel_id.send_message(&ctx.http, |m| m.embed(|embed| {
-                        embed.0 = invalid_channel_embed(String::from("Missing permissions to delete messages in that channel.")).0;
-                        embed
-                    })).await?;
-                    return Ok(());
-                }
+        // The cached count is newer than the database one
+        let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+        let counting_text = database_guild.counting.iter()
+            .map(|counting| {
+                let count = counting_cache.get(&ChannelId::from(counting.channel as u64)).map(|cached_counting| cached_counting.count).unwrap_or(counting.count);
+                format!("<#{}> at **{}**, Record {}, Counts in {}. {}.", counting.channel, count, counting.high_score, counting.mode, counting.rules)
+            })
+            .collect::<Vec<String>>().join("\n");
+        let mut embed = CreateEmbed::default();
+        embed.title("Counting channels")
+            .description(counting_text)
+            .color(Colour::BLITZ_BLUE);
+        source.send_embed(ctx, embed).await?;
+    }
 
-                // Insert it into the database
-                let mut database_guild = DatabaseGuild::get_or_insert_new(ctx, msg.guild_id.unwrap().0 as i64).await;
-                database_guild.counting = Some(GuildCounting { channel: new_channel.0 as i64, count: 0 });
+    Ok(())
+}
 
-                DatabaseGuild::insert_or_replace(ctx, database_guild).await;
+pub(crate) async fn add_counting_channel(ctx: &Context, source: &CommandSource<'_>, channel: ChannelId) -> CommandResult {
+    let guild_id = source.guild_id().unwrap();
+    let guild = guild_id.to_guild_cached(&ctx).await.unwrap();
+    if !guild.channels.contains_key(&channel) {
+        source.send_embed(ctx, invalid_channel_embed(String::from("Channel not found in this guild."))).await?;
+        return Ok(());
+    } else if !guild.user_permissions_in(guild.channels.get(&channel).unwrap(), guild.members.get(&ctx.http.get_current_user().await?.id).unwrap())
+        .unwrap().contains(Permissions::MANAGE_MESSAGES) {
+        source.send_embed(ctx, invalid_channel_embed(String::from("Missing permissions to delete messages in that channel."))).await?;
+        return Ok(());
+    }
 
-                // Insert into CountingCache
-                ctx.data.read().await.get::<CountingCache>().unwrap().insert(new_channel, 0);
+    let database_guild = DatabaseGuild::get_or_insert_new(ctx, guild_id.0 as i64).await;
+    if database_guild.counting.iter().any(|counting| counting.channel == channel.0 as i64) {
+        source.say(ctx, format!(":no_entry_sign: <#{}> is already a counting channel.", channel.0)).await?;
+        return Ok(());
+    }
 
-                msg.channel_id.say(ctx, format!(":white_check_mark: Set the counting channel to <#{}>", new_channel.0)).await?;
-            }
-        }
+    // Put it in the database and the cache
+    let new_counting = GuildCounting::new(channel, Default::default(), Default::default());
+    DatabaseGuild::add_counting(ctx, guild_id.0 as i64, &new_counting).await;
+
+    let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+    counting_cache.insert(channel, new_counting);
+
+    source.say(ctx, format!(":white_check_mark: Added the counting channel <#{}>", channel.0)).await?;
+
+    Ok(())
+}
+
+pub(crate) async fn remove_counting_channel(ctx: &Context, source: &CommandSource<'_>, channel: ChannelId) -> CommandResult {
+    let guild_id = source.guild_id().unwrap();
+    let database_guild = DatabaseGuild::get_or_insert_new(ctx, guild_id.0 as i64).await;
+    if !database_guild.counting.iter().any(|counting| counting.channel == channel.0 as i64) {
+        source.say(ctx, format!(":no_entry_sign: <#{}> isn't a counting channel.", channel.0)).await?;
+        return Ok(());
+    }
+
+    DatabaseGuild::remove_counting(ctx, guild_id.0 as i64, channel.0 as i64).await;
+
+    // Remove from cache, The streak it was on ends with it
+    let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+    if let Some((_, old_counting)) = counting_cache.remove(&channel) {
+        DatabaseCountingStats::end_streak(ctx, guild_id.0 as i64, old_counting.channel, old_counting.count).await;
+    }
+
+    source.say(ctx, format!(":white_check_mark: Removed the counting channel <#{}>", channel.0)).await?;
+
+    Ok(())
+}
+
+pub(crate) async fn update_counting_settings(ctx: &Context, source: &CommandSource<'_>, channel: Option<ChannelId>, allow_consecutive: Option<bool>,
+                                             on_fail: Option<CountingFailAction>, mode: Option<CountingMode>) -> CommandResult {
+    let guild = source.guild_id().unwrap().0 as i64;
+    let mut database_guild = DatabaseGuild::get_or_insert_new(ctx, guild).await;
+    let channel = match channel.map(Ok).unwrap_or_else(|| pick_counting_channel(&database_guild.counting, source.channel_id())) {
+        Ok(channel) => channel,
+        Err(why) => { source.say(ctx, format!(":no_entry_sign: {}", why)).await?; return Ok(()); }
+    };
+    let counting = match database_guild.counting.iter_mut().find(|counting| counting.channel == channel.0 as i64) {
+        Some(counting) => counting,
+        None => { source.say(ctx, format!(":no_entry_sign: <#{}> isn't a counting channel.", channel.0)).await?; return Ok(()); }
+    };
+
+    if let Some(allow_consecutive) = allow_consecutive {
+        counting.rules.allow_consecutive = allow_consecutive;
+    }
+    if let Some(on_fail) = on_fail {
+        counting.rules.on_fail = on_fail;
+    }
+    if let Some(mode) = mode {
+        counting.mode = mode;
+    }
+    let (rules, mode) = (counting.rules, counting.mode);
+
+    // Only the rules and mode change, The cached count can be newer than the database one
+    let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+    if let Some(mut cached_counting) = counting_cache.get_mut(&channel) {
+        cached_counting.rules = rules;
+        cached_counting.mode = mode;
+    }
+    DatabaseGuild::update_counting_settings(ctx, guild, channel.0 as i64, rules, mode).await;
+
+    source.say(ctx, format!(":white_check_mark: <#{}> now {} and counts in {}.", channel.0, rules.to_string().to_lowercase(), mode)).await?;
+
+    Ok(())
+}
+
+#[command]
+#[description = "Posts in a channel when the weather at an airport changes flight rules or the wind crosses a limit in knots. Use `off` as the conditions to remove it, Or leave everything out to list them."]
+#[required_permissions("ADMINISTRATOR")]
+#[only_in("guilds")]
+#[aliases("weatheralert", "wxalerts")]
+#[usage = "channel ident rules|wind:<kt>|gust:<kt>|off"]
+async fn wxalert(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let source = CommandSource::from(msg);
+
+    // List the alerts
+    if args.is_empty() {
+        return list_weather_alerts(ctx, &source).await;
+    }
+
+    // Parse the arguments
+    let (channel, ident) = match (args.single::<ChannelId>(), args.single::<String>()) {
+        (Ok(channel), Ok(ident)) => (channel, ident),
+        (Err(why), _) => { source.send_embed(ctx, invalid_channel_embed(why.to_string())).await?; return Ok(()); }
+        (_, Err(_)) => { source.say(ctx, ":no_entry_sign: Couldn't parse the ICAO ident, Are you sure it's valid?").await?; return Ok(()); }
+    };
+
+    if args.rest().eq_ignore_ascii_case("off") || args.rest().eq_ignore_ascii_case("remove") {
+        remove_weather_alert(ctx, &source, channel, ident).await
+    } else {
+        add_weather_alert(ctx, &source, channel, ident, args.rest()).await
     }
+}
+
+pub(crate) async fn list_weather_alerts(ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
+    let database_guild = DatabaseGuild::get_or_insert_new(ctx, source.guild_id().unwrap().0 as i64).await;
+
+    if database_guild.weather_alerts.is_empty() {
+        source.say(ctx, ":information_source: There are no weather alerts in this server.").await?;
+    } else {
+        let alerts_text = database_guild.weather_alerts.iter()
+            .map(|alert| format!("<#{}> {}: {}", alert.channel, alert.ident, alert.conditions))
+            .collect::<Vec<String>>().join("\n");
+        let mut embed = CreateEmbed::default();
+        embed.title("Weather alerts")
+            .description(alerts_text)
+            .color(Colour::BLITZ_BLUE);
+        source.send_embed(ctx, embed).await?;
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn remove_weather_alert(ctx: &Context, source: &CommandSource<'_>, channel: ChannelId, ident: String) -> CommandResult {
+    let guild_id = source.guild_id().unwrap();
+    let mut database_guild = DatabaseGuild::get_or_insert_new(ctx, guild_id.0 as i64).await;
+    let ident = ident.to_uppercase();
+
+    let alert_count = database_guild.weather_alerts.len();
+    database_guild.weather_alerts.retain(|alert| !(alert.channel == channel.0 as i64 && alert.ident == ident));
+    if database_guild.weather_alerts.len() == alert_count {
+        source.say(ctx, format!(":no_entry_sign: There is no weather alert for {} in <#{}>.", ident, channel.0)).await?;
+        return Ok(());
+    }
+
+    DatabaseGuild::set_weather_alerts(ctx, guild_id.0 as i64, &database_guild.weather_alerts).await;
+    ctx.data.read().await.get::<WeatherAlertCache>().unwrap().insert(guild_id, database_guild.weather_alerts);
+
+    source.say(ctx, format!(":white_check_mark: Removed the weather alert for {} in <#{}>", ident, channel.0)).await?;
+
+    Ok(())
+}
+
+pub(crate) async fn add_weather_alert(ctx: &Context, source: &CommandSource<'_>, channel: ChannelId, ident: String, conditions: &str) -> CommandResult {
+    let guild_id = source.guild_id().unwrap();
+    let conditions = match AlertConditions::parse(conditions) {
+        Ok(conditions) => conditions,
+        Err(why) => { source.say(ctx, format!(":no_entry_sign: {}", why)).await?; return Ok(()); }
+    };
+
+    let guild = guild_id.to_guild_cached(&ctx).await.unwrap();
+    if !guild.channels.contains_key(&channel) {
+        source.send_embed(ctx, invalid_channel_embed(String::from("Channel not found in this guild."))).await?;
+        return Ok(());
+    }
+
+    // Make sure the station exists and reports weather
+    let provider = ctx.data.read().await.get::<AviationProviderContainer>().cloned().unwrap();
+    let ident = match provider.metar(&ident).await {
+        Ok(metar) => metar.station,
+        Err(why) => { source.say(ctx, format!(":no_entry_sign: Couldn't get the METAR for {}: {}", ident.to_uppercase(), why)).await?; return Ok(()); }
+    };
+
+    // Replace the alert if the channel already watches this station
+    let mut database_guild = DatabaseGuild::get_or_insert_new(ctx, guild_id.0 as i64).await;
+    database_guild.weather_alerts.retain(|alert| !(alert.channel == channel.0 as i64 && alert.ident == ident));
+    database_guild.weather_alerts.push(WeatherAlert { channel: channel.0 as i64, ident: ident.clone(), conditions });
+
+    DatabaseGuild::set_weather_alerts(ctx, guild_id.0 as i64, &database_guild.weather_alerts).await;
+    ctx.data.read().await.get::<WeatherAlertCache>().unwrap().insert(guild_id, database_guild.weather_alerts);
+
+    source.say(ctx, format!(":white_check_mark: <#{}> now gets alerts for {} on {}", channel.0, ident, conditions)).await?;
 
     Ok(())
 }
+
 fn invalid_channel_embed(why: String) -> CreateEmbed {
     let mut embed = CreateEmbed::default();
 
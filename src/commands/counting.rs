@@ -0,0 +1,136 @@
+use std::time::Duration;
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::framework::standard::{CommandResult, macros::command, Args};
+use serenity::utils::Colour;
+use serenity::builder::CreateEmbed;
+use crate::helpers::paginator::Paginator;
+use crate::helpers::database_helper::{DatabaseGuild, DatabaseCountingStats};
+use crate::helpers::global_data::CountingCache;
+use crate::helpers::command_source::CommandSource;
+use crate::helpers::counting::pick_counting_channel;
+
+const RANKS_PER_PAGE: usize = 10;
+// Everyone can page through the leaderboard, So it isn't kept open as long as other menus
+const LEADERBOARD_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+#[command]
+#[aliases("top", "lb")]
+#[only_in("guilds")]
+#[max_args(1)]
+#[usage = "[channel]"]
+#[description = "Shows who counted the most in a counting channel, This one or the only one of the server when it's left out"]
+async fn leaderboard(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap().0 as i64;
+    let channel = match counting_channel(ctx, msg, &mut args).await? {
+        Some(channel) => channel,
+        None => return Ok(()),
+    };
+
+    let ranked_count = DatabaseCountingStats::count_for_channel(ctx, guild, channel.0 as i64).await as usize;
+    if ranked_count == 0 {
+        msg.channel_id.say(ctx, format!(":information_source: Nobody has counted in <#{}> yet.", channel.0)).await?;
+        return Ok(());
+    }
+
+    // Only the pages that get looked at are fetched
+    let current_count = current_count(ctx, channel).await;
+    let page_count = ranked_count.div_ceil(RANKS_PER_PAGE);
+    Paginator::lazy(page_count, |index| async move {
+        let page_stats = DatabaseCountingStats::get_page_for_channel(ctx, guild, channel.0 as i64, (index * RANKS_PER_PAGE) as i64, RANKS_PER_PAGE as i64).await;
+        create_leaderboard_embed(channel, index * RANKS_PER_PAGE, &page_stats, current_count)
+    }).for_everyone().timeout(LEADERBOARD_TIMEOUT).send(ctx, &CommandSource::from(msg)).await
+}
+
+#[command]
+#[only_in("guilds")]
+#[max_args(1)]
+#[usage = "[@user]"]
+#[description = "Shows how much someone counted and how often they got it wrong, You when it's left out"]
+async fn stats(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild = msg.guild_id.unwrap().0 as i64;
+    let user = if args.is_empty() {
+        msg.author.clone()
+    } else {
+        match args.single::<UserId>() {
+            Ok(user_id) => user_id.to_user(ctx).await?,
+            Err(_) => { msg.channel_id.say(ctx, ":no_entry_sign: Couldn't find that user, Mention them or use their ID.").await?; return Ok(()); }
+        }
+    };
+
+    let counting_stats = DatabaseCountingStats::get_for_user(ctx, guild, user.id.0 as i64).await;
+    if counting_stats.is_empty() {
+        msg.channel_id.say(ctx, format!(":information_source: {} hasn't counted in this server yet.", user.name)).await?;
+        return Ok(());
+    }
+
+    // A page for every counting channel they counted in
+    let mut pages = Vec::new();
+    for channel_stats in &counting_stats {
+        let channel = ChannelId::from(channel_stats.channel as u64);
+        let rank = DatabaseCountingStats::rank(ctx, channel_stats).await;
+        pages.push(create_stats_embed(&user.name, channel_stats, rank, current_count(ctx, channel).await));
+    }
+
+    Paginator::new(pages).send(ctx, &CommandSource::from(msg)).await
+}
+
+// The channel from the arguments, Then this channel when it's a counting channel and otherwise the only counting channel of the server
+async fn counting_channel(ctx: &Context, msg: &Message, args: &mut Args) -> Result<Option<ChannelId>, SerenityError> {
+    if !args.is_empty() {
+        return match args.single::<ChannelId>() {
+            Ok(channel) => Ok(Some(channel)),
+            Err(_) => { msg.channel_id.say(ctx, ":no_entry_sign: That isn't a channel, Mention it or use its ID.").await?; Ok(None) }
+        };
+    }
+
+    let database_guild = DatabaseGuild::get_or_insert_new(ctx, msg.guild_id.unwrap().0 as i64).await;
+    match pick_counting_channel(&database_guild.counting, msg.channel_id) {
+        Ok(channel) => Ok(Some(channel)),
+        Err(why) => { msg.channel_id.say(ctx, format!(":no_entry_sign: {}", why)).await?; Ok(None) }
+    }
+}
+
+// The count of the streak that's still going, Zero when the channel doesn't count anymore
+async fn current_count(ctx: &Context, channel: ChannelId) -> i64 {
+    let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+    let current_count = counting_cache.get(&channel).map(|counting| counting.count).unwrap_or(0);
+
+    current_count
+}
+
+// The streak that's still going counts too when they're in it
+fn best_streak(counting_stats: &DatabaseCountingStats, current_count: i64) -> i64 {
+    if counting_stats.in_streak { counting_stats.best_streak.max(current_count) } else { counting_stats.best_streak }
+}
+
+fn create_leaderboard_embed(channel: ChannelId, first_rank: usize, page_stats: &[DatabaseCountingStats], current_count: i64) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let ranks_text = page_stats.iter().enumerate()
+        .map(|(index, counting_stats)| format!("**{}.** <@{}> {} counts, {} mistakes, Best streak {}", first_rank + index + 1, counting_stats.user,
+                                               counting_stats.counts, counting_stats.mistakes, best_streak(counting_stats, current_count)))
+        .collect::<Vec<String>>().join("\n");
+    embed.title("Counting leaderboard")
+        .description(format!("<#{}>\n\n{}", channel.0, ranks_text))
+        .color(Colour::BLITZ_BLUE);
+
+    embed
+}
+
+fn create_stats_embed(user_name: &str, counting_stats: &DatabaseCountingStats, rank: i64, current_count: i64) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let attempts = counting_stats.counts + counting_stats.mistakes;
+    let accuracy = if attempts > 0 { counting_stats.counts as f64 / attempts as f64 * 100.0 } else { 0.0 };
+    embed.title(format!("Counting stats of {}", user_name))
+        .description(format!("<#{}>", counting_stats.channel))
+        .field("Rank", format!("#{}", rank), true)
+        .field("Counts", counting_stats.counts, true)
+        .field("Mistakes", counting_stats.mistakes, true)
+        .field("Accuracy", format!("{:.1}%", accuracy), true)
+        .field("Best streak", best_streak(counting_stats, current_count), true)
+        .color(Colour::BLITZ_BLUE);
+
+    embed
+}
@@ -0,0 +1,233 @@
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::framework::standard::{CommandResult, macros::command, Args};
+use serenity::utils::Colour;
+use serenity::builder::CreateEmbed;
+use serenity::http::AttachmentType;
+use std::collections::{BTreeMap, HashMap};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use crate::commands::aviation::resolve_station;
+use crate::helpers::paginator::Paginator;
+use crate::helpers::aviation_helper::night_minutes;
+use crate::helpers::database_helper::DatabaseFlight;
+use crate::helpers::command_source::CommandSource;
+
+const FLIGHTS_PER_PAGE: usize = 10;
+const MAX_TOTAL_LINES: usize = 12;
+const MAX_FLIGHT_MINUTES: i64 = 24 * 60;
+
+#[command("add")]
+#[min_args(4)]
+#[usage = "<from> <to> <aircraft> <duration> [YYYY-MM-DDTHH:MM] [notes]"]
+#[description = "Logs a flight, The duration is like `1:30`, `1.5` or `90m` up to a day and the landing time is now unless given in UTC"]
+async fn log_add(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    // Parse the arguments
+    let from = args.single::<String>()?;
+    let to = args.single::<String>()?;
+    let aircraft = args.single::<String>()?.to_uppercase();
+    let duration_minutes = match parse_duration(&args.single::<String>()?) {
+        Some(duration_minutes) => duration_minutes,
+        None => {
+            msg.channel_id.say(ctx, ":no_entry_sign: The duration has to look like `1:30`, `1.5` or `90m` and be at most a day.").await?;
+            return Ok(());
+        }
+    };
+    // A landing time can come before the notes
+    let landed_at = match args.current().and_then(parse_landing_time) {
+        Some(landed_at) => {
+            args.advance();
+            landed_at
+        }
+        None => msg.timestamp,
+    };
+    let notes = Some(args.rest().trim().to_string()).filter(|notes| !notes.is_empty());
+
+    // Make sure both airports exist, Their positions are needed for the night time
+    let from_station = match resolve_station(ctx, &CommandSource::from(msg), &from).await? {
+        Some(from_station) => from_station,
+        None => return Ok(()),
+    };
+    let to_station = match resolve_station(ctx, &CommandSource::from(msg), &to).await? {
+        Some(to_station) => to_station,
+        None => return Ok(()),
+    };
+
+    let night_minutes = night_minutes((from_station.latitude as f64, from_station.longitude as f64), (to_station.latitude as f64, to_station.longitude as f64),
+                                      landed_at - Duration::minutes(duration_minutes), duration_minutes);
+    let database_flight = DatabaseFlight {
+        _id: None,
+        user: msg.author.id.0 as i64,
+        from: from_station.icao,
+        to: to_station.icao,
+        aircraft,
+        landed_at: bson::DateTime(landed_at),
+        duration_minutes,
+        night_minutes,
+        notes,
+    };
+    DatabaseFlight::insert(ctx, &database_flight).await;
+
+    msg.channel_id.say(ctx, format!(":white_check_mark: Logged {} to {} in {}, {} of which {} at night.", database_flight.from, database_flight.to,
+                                    database_flight.aircraft, format_minutes(duration_minutes), format_minutes(night_minutes))).await?;
+
+    Ok(())
+}
+
+#[command("list")]
+#[description = "Shows your logged flights, The latest first"]
+async fn log_list(ctx: &Context, msg: &Message) -> CommandResult {
+    let user = msg.author.id.0 as i64;
+    let flight_count = DatabaseFlight::count_for_user(ctx, user).await as usize;
+    if flight_count == 0 {
+        msg.channel_id.say(ctx, ":information_source: You haven't logged any flights yet, Add one with `log add`.").await?;
+        return Ok(());
+    }
+
+    // Only the pages that get looked at are fetched
+    let page_count = flight_count.div_ceil(FLIGHTS_PER_PAGE);
+    let user_name = &msg.author.name;
+    Paginator::lazy(page_count, |index| async move {
+        let page_flights = DatabaseFlight::get_page_for_user(ctx, user, (index * FLIGHTS_PER_PAGE) as i64, FLIGHTS_PER_PAGE as i64).await;
+        create_flights_embed(user_name, &page_flights)
+    }).send(ctx, &CommandSource::from(msg)).await
+}
+
+#[command("totals")]
+#[aliases("total")]
+#[description = "Shows your total flight time by aircraft, By month and day or night"]
+async fn log_totals(ctx: &Context, msg: &Message) -> CommandResult {
+    let database_flights = DatabaseFlight::get_for_user(ctx, msg.author.id.0 as i64).await;
+    if database_flights.is_empty() {
+        msg.channel_id.say(ctx, ":information_source: You haven't logged any flights yet, Add one with `log add`.").await?;
+        return Ok(());
+    }
+
+    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+        e.0 = create_totals_embed(&msg.author.name, &database_flights).0;
+        e
+    })).await?;
+
+    Ok(())
+}
+
+#[command("export")]
+#[aliases("csv")]
+#[description = "Sends your logbook as a CSV file"]
+async fn log_export(ctx: &Context, msg: &Message) -> CommandResult {
+    let database_flights = DatabaseFlight::get_for_user(ctx, msg.author.id.0 as i64).await;
+    if database_flights.is_empty() {
+        msg.channel_id.say(ctx, ":information_source: You haven't logged any flights yet, Add one with `log add`.").await?;
+        return Ok(());
+    }
+
+    // Oldest first like a paper logbook
+    let mut csv_writer = csv::Writer::from_writer(Vec::new());
+    csv_writer.write_record(["landed_at", "from", "to", "aircraft", "duration_minutes", "night_minutes", "notes"])?;
+    for database_flight in database_flights.iter().rev() {
+        csv_writer.write_record([
+            database_flight.landed_at.0.format("%Y-%m-%dT%H:%MZ").to_string(),
+            database_flight.from.clone(),
+            database_flight.to.clone(),
+            database_flight.aircraft.clone(),
+            database_flight.duration_minutes.to_string(),
+            database_flight.night_minutes.to_string(),
+            database_flight.notes.clone().unwrap_or_default(),
+        ])?;
+    }
+    let csv_data = csv_writer.into_inner()?;
+
+    msg.channel_id.send_files(&ctx.http, vec![AttachmentType::Bytes { data: csv_data.into(), filename: String::from("logbook.csv") }], |m| {
+        m.content(format!(":white_check_mark: Your logbook with {} flights.", database_flights.len()))
+    }).await?;
+
+    Ok(())
+}
+
+fn create_flights_embed(user_name: &str, database_flights: &[DatabaseFlight]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let flights_text = database_flights.iter()
+        .map(|database_flight| {
+            let mut line = format!("`{}` **{}** → **{}** {} {}", database_flight.landed_at.0.format("%Y-%m-%d"), database_flight.from, database_flight.to,
+                                   database_flight.aircraft, format_minutes(database_flight.duration_minutes));
+            if database_flight.night_minutes > 0 { line += &format!(" ({} night)", format_minutes(database_flight.night_minutes)) }
+            if let Some(notes) = &database_flight.notes { line += &format!("\n> {}", notes) }
+            line
+        })
+        .collect::<Vec<String>>().join("\n");
+    embed.title(format!("Logbook of {}", user_name))
+        .description(flights_text)
+        .color(Colour::BLITZ_BLUE);
+
+    embed
+}
+
+fn create_totals_embed(user_name: &str, database_flights: &[DatabaseFlight]) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    let total_minutes = database_flights.iter().map(|database_flight| database_flight.duration_minutes).sum::<i64>();
+    let night_minutes = database_flights.iter().map(|database_flight| database_flight.night_minutes).sum::<i64>();
+    let mut aircraft_minutes: HashMap<&str, i64> = HashMap::new();
+    let mut month_minutes: BTreeMap<String, i64> = BTreeMap::new();
+    for database_flight in database_flights {
+        *aircraft_minutes.entry(&database_flight.aircraft).or_default() += database_flight.duration_minutes;
+        *month_minutes.entry(database_flight.landed_at.0.format("%Y-%m").to_string()).or_default() += database_flight.duration_minutes;
+    }
+
+    // Most flown aircraft and the latest months first
+    let mut aircraft_minutes = aircraft_minutes.into_iter().collect::<Vec<(&str, i64)>>();
+    aircraft_minutes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let aircraft_text = aircraft_minutes.iter().take(MAX_TOTAL_LINES)
+        .map(|(aircraft, minutes)| format!("{}: {}", aircraft, format_minutes(*minutes)))
+        .collect::<Vec<String>>().join("\n");
+    let month_text = month_minutes.iter().rev().take(MAX_TOTAL_LINES)
+        .map(|(month, minutes)| format!("{}: {}", month, format_minutes(*minutes)))
+        .collect::<Vec<String>>().join("\n");
+
+    embed.title(format!("Flight time of {}", user_name))
+        .field("Total", format!("Flights: {}\nTime: {}\nDay: {}\nNight: {}", database_flights.len(), format_minutes(total_minutes),
+                                format_minutes(total_minutes - night_minutes), format_minutes(night_minutes)), true)
+        .field("By aircraft", aircraft_text, true)
+        .field("By month", month_text, true)
+        .footer(|f| f.text("Night is between the end and start of civil twilight"))
+        .color(Colour::BLITZ_BLUE);
+
+    embed
+}
+
+// Reads `1:30`, `1.5`, `1.5h`, `90m` or `1h30m` as minutes, Up to a day
+fn parse_duration(argument: &str) -> Option<i64> {
+    let argument = argument.to_lowercase();
+    let minutes = if let Some((hours, minutes)) = argument.split_once(':') {
+        hours.parse::<i64>().ok()?.checked_mul(60)?.checked_add(minutes.parse::<i64>().ok().filter(|minutes| (0..60).contains(minutes))?)?
+    } else if let Some((hours, minutes)) = argument.split_once('h') {
+        let minutes = minutes.trim_end_matches('m');
+        let minutes = if minutes.is_empty() { 0 } else { minutes.parse::<i64>().ok().filter(|minutes| *minutes >= 0)? };
+        hours_to_minutes(hours)?.checked_add(minutes)?
+    } else if let Some(minutes) = argument.strip_suffix('m') {
+        minutes.parse::<i64>().ok()?
+    } else {
+        hours_to_minutes(&argument)?
+    };
+
+    Some(minutes).filter(|minutes| (1..=MAX_FLIGHT_MINUTES).contains(minutes))
+}
+
+// Hours with an optional fraction, Infinity, NaN and anything longer than a flight can be aren't
+fn hours_to_minutes(hours: &str) -> Option<i64> {
+    let minutes = hours.parse::<f64>().ok()? * 60.0;
+
+    if minutes.is_finite() && minutes.abs() <= MAX_FLIGHT_MINUTES as f64 { Some(minutes.round() as i64) } else { None }
+}
+
+// Reads `2021-06-21T14:30` or just `2021-06-21` in UTC
+fn parse_landing_time(argument: &str) -> Option<DateTime<Utc>> {
+    let landed_at = NaiveDateTime::parse_from_str(argument, "%Y-%m-%dT%H:%M").ok()
+        .or_else(|| NaiveDate::parse_from_str(argument, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(12, 0, 0)))?;
+
+    Some(Utc.from_utc_datetime(&landed_at))
+}
+
+fn format_minutes(minutes: i64) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
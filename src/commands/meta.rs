@@ -7,16 +7,21 @@ use serenity::framework::standard::{
 use serenity::constants::GATEWAY_VERSION;
 use std::time::Instant;
 use crate::helpers::database_helper::DatabaseGuild;
-use crate::helpers::global_data::Uptime;
+use crate::helpers::global_data::{Uptime, AviationCache};
 use crate::helpers::general_helper::seconds_to_days;
 use serenity::builder::CreateEmbed;
 use serenity::utils::Colour;
 use serenity::model::Permissions;
+use crate::helpers::command_source::CommandSource;
 
 #[command]
 #[description = "Pong!"]
 #[aliases("pong", "latency")]
 async fn ping(ctx: &Context, msg: &Message) -> CommandResult {
+    run_ping(ctx, &CommandSource::from(msg)).await
+}
+
+pub(crate) async fn run_ping(ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
     let gateway_url = format!("https://discord.com/api/v{}/gateway", GATEWAY_VERSION);
 
     // Get latency, Get the gateway URL.
@@ -26,24 +31,24 @@ async fn ping(ctx: &Context, msg: &Message) -> CommandResult {
 
     // Post latency, Send a message.
     let now = Instant::now();
-    let mut sent_message = msg.channel_id
-        .say(&ctx.http, "<a:loading:776804948633059338> Calculating latency...").await?;
+    let mut sent_message = source
+        .say(ctx, "<a:loading:776804948633059338> Calculating latency...").await?;
     let post_latency = now.elapsed().as_millis();
 
     // Database guild find latency, Absolutely cursed.
     let mut guild_string = String::from("");
-    if msg.guild_id != None {
+    if source.guild_id() != None {
         let now = Instant::now();
-        DatabaseGuild::get(ctx, msg.guild_id.unwrap().0 as i64).await;
+        DatabaseGuild::get(ctx, source.guild_id().unwrap().0 as i64).await;
         let get_guild_latency = now.elapsed().as_millis();
         guild_string = format!("\nMONGO GET GUILD: {}ms", get_guild_latency);
     }
 
-    sent_message.edit(&ctx, |m| m.content("").embed(|e| {
-        e.title("Pong! Latency");
-        e.description(format!("REST GET: {}ms\nREST POST: {}ms{}", get_latency, post_latency, guild_string));
-        e.color(Colour::BLURPLE)
-    })).await?;
+    let mut embed = CreateEmbed::default();
+    embed.title("Pong! Latency");
+    embed.description(format!("REST GET: {}ms\nREST POST: {}ms{}", get_latency, post_latency, guild_string));
+    embed.color(Colour::BLURPLE);
+    source.edit(ctx, &mut sent_message, "", Some(embed)).await?;
 
     Ok(())
 }
@@ -52,7 +57,11 @@ async fn ping(ctx: &Context, msg: &Message) -> CommandResult {
 #[description = "Some information about the bot."]
 #[aliases("info", "stats", "uptime", "botinfo")]
 async fn about(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut message = msg.channel_id.say(&ctx.http, "<a:loading:776804948633059338> Collecting data...").await?;
+    run_about(ctx, &CommandSource::from(msg)).await
+}
+
+pub(crate) async fn run_about(ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
+    let mut message = source.say(ctx, "<a:loading:776804948633059338> Collecting data...").await?;
     let mut embed = CreateEmbed::default();
 
     // Basic info
@@ -108,10 +117,12 @@ async fn about(ctx: &Context, msg: &Message) -> CommandResult {
     };
     embed.field("Uptime", uptime, true);
 
-    message.edit(&ctx, |m| m.content("").embed(|e| {
-        e.0 = embed.0;
-        e
-    })).await?;
+    // Aviation response cache
+    let aviation_cache = ctx.data.read().await.get::<AviationCache>().cloned().unwrap();
+    embed.field("Aviation cache", format!("{} hits\n{} misses\n{} entries",
+                                          aviation_cache.hits(), aviation_cache.misses(), aviation_cache.entry_count()), true);
+
+    source.edit(ctx, &mut message, "", Some(embed)).await?;
 
     Ok(())
 }
@@ -121,9 +132,13 @@ async fn about(ctx: &Context, msg: &Message) -> CommandResult {
 #[only_in("guilds")]
 #[aliases("server", "guild", "guildinfo")]
 async fn serverinfo(ctx: &Context, msg: &Message) -> CommandResult {
-    let mut message = msg.channel_id.say(&ctx.http, "<a:loading:776804948633059338> Loading information about the guild...").await?;
+    run_serverinfo(ctx, &CommandSource::from(msg)).await
+}
+
+pub(crate) async fn run_serverinfo(ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
+    let mut message = source.say(ctx, "<a:loading:776804948633059338> Loading information about the guild...").await?;
 
-    let cached_guild = msg.guild_id.unwrap().to_guild_cached(&ctx.cache).await.unwrap();
+    let cached_guild = source.guild_id().unwrap().to_guild_cached(&ctx.cache).await.unwrap();
 
     let mut embed = CreateEmbed::default();
 
@@ -131,7 +146,7 @@ async fn serverinfo(ctx: &Context, msg: &Message) -> CommandResult {
         .thumbnail(&cached_guild.icon_url().unwrap_or(String::new()))
         .color(Colour::BLURPLE)
         .footer(|f| f.text(format!("ID: {} Created", cached_guild.id.0)))
-        .timestamp(&msg.guild_id.unwrap().created_at());
+        .timestamp(&source.guild_id().unwrap().created_at());
 
     // Get the guild owner
     let owner: User = cached_guild.owner_id.to_user(&ctx).await?;
@@ -208,10 +223,7 @@ async fn serverinfo(ctx: &Context, msg: &Message) -> CommandResult {
     embed.field("Roles", format!("{} roles", cached_guild.roles.len()), true);
 
     // Send the embed
-    message.edit(&ctx, |f| f.content("").embed(|e| {
-        e.0 = embed.0;
-        e
-    })).await?;
+    source.edit(ctx, &mut message, "", Some(embed)).await?;
 
     Ok(())
 }
@@ -219,6 +231,10 @@ async fn serverinfo(ctx: &Context, msg: &Message) -> CommandResult {
 #[command]
 #[description = "Gives you an invite link"]
 async fn invite(ctx: &Context, msg: &Message) -> CommandResult {
+    run_invite(ctx, &CommandSource::from(msg)).await
+}
+
+pub(crate) async fn run_invite(ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
     let mut permissions = Permissions::default();
     permissions.set(Permissions::READ_MESSAGES, true);
     permissions.set(Permissions::SEND_MESSAGES, true);
@@ -233,17 +249,17 @@ async fn invite(ctx: &Context, msg: &Message) -> CommandResult {
         Err(why) => {
             println!("Error creating invite url: {:?}", why);
 
-            msg.channel_id.say(&ctx, ":no_entry_sign: Error creating invite url").await?;
+            source.say(ctx, ":no_entry_sign: Error creating invite url").await?;
 
             return Ok(());
         }
     };
 
-    msg.channel_id.send_message(&ctx, |m| m.embed(|e| {
-        e.title("Invite link")
-            .url(invite_url)
-            .color(Colour::BLURPLE)
-    })).await?;
+    let mut embed = CreateEmbed::default();
+    embed.title("Invite link")
+        .url(invite_url)
+        .color(Colour::BLURPLE);
+    source.send_embed(ctx, embed).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -1,4 +1,9 @@
 pub mod meta;
 pub mod moderation;
 pub mod configuration;
-pub mod aviation;
\ No newline at end of file
+pub mod aviation;
+pub mod settings;
+pub mod airport_database;
+pub mod logbook;
+pub mod counting;
+pub mod slash;
\ No newline at end of file
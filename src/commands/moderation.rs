@@ -1,6 +1,7 @@
 use serenity::prelude::*;
 use serenity::model::prelude::*;
 use serenity::framework::standard::{CommandResult, macros::command, Args};
+use crate::helpers::command_source::CommandSource;
 
 #[command]
 #[required_permissions("MANAGE_MESSAGES")]
@@ -12,24 +13,26 @@ async fn purge(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     let delete_num = args.single::<u64>();
     match delete_num {
         Err(_) => { msg.channel_id.say(ctx, ":no_entry_sign: The value provided was not a valid number").await?; },
-        Ok(delete_n) => {
-            let mut find_msg = msg.channel_id
-                .say(ctx, format!(":hourglass: Finding and deleting {} messages...", delete_n)).await?;
+        Ok(delete_n) => run_purge(ctx, &CommandSource::from(msg), delete_n).await?,
+    }
 
-            let channel = &msg.channel(ctx).await.unwrap().guild().unwrap();
+    Ok(())
+}
 
-            let messages = &channel
-                .messages(ctx, |r| r.before(&msg.id).limit(delete_n)).await?;
-            let message_ids = messages.iter().map(|m| m.id).collect::<Vec<MessageId>>();
+pub(crate) async fn run_purge(ctx: &Context, source: &CommandSource<'_>, delete_n: u64) -> CommandResult {
+    let mut find_msg = source
+        .say(ctx, format!(":hourglass: Finding and deleting {} messages...", delete_n)).await?;
 
-            channel.delete_messages(ctx, message_ids).await?;
+    let channel = &source.channel_id().to_channel(ctx).await?.guild().unwrap();
 
-            find_msg.edit(ctx, |m| {
-                m.content(format!(":white_check_mark: Deleted {} messages", delete_n));
-                m
-            }).await?;
-        }
-    }
+    // Everything before the command, So the reply isn't deleted
+    let messages = &channel
+        .messages(ctx, |r| r.before(MessageId::from(source.id())).limit(delete_n)).await?;
+    let message_ids = messages.iter().map(|m| m.id).collect::<Vec<MessageId>>();
+
+    channel.delete_messages(ctx, message_ids).await?;
+
+    source.edit(ctx, &mut find_msg, format!(":white_check_mark: Deleted {} messages", delete_n), None).await?;
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,85 @@
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::framework::standard::{CommandResult, macros::command, Args};
+use serenity::utils::Colour;
+use crate::helpers::database_helper::DatabaseUser;
+use crate::helpers::command_source::CommandSource;
+use crate::commands::aviation::resolve_station;
+use crate::models::units::UserUnits;
+
+#[command]
+#[description = "Shows your settings for the aviation commands"]
+async fn show(ctx: &Context, msg: &Message) -> CommandResult {
+    let database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+
+    msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| {
+        e.title(format!("Settings for {}", msg.author.name))
+            .color(Colour::BLITZ_BLUE)
+            .field("Units", format_units(&database_user.units), true)
+            .field("Home airport", database_user.home_airport.as_deref().unwrap_or("None"), true)
+            .footer(|f| f.text("Change them with the units and home settings commands."))
+    })).await?;
+
+    Ok(())
+}
+
+#[command]
+#[min_args(1)]
+#[usage = "unit [unit...]"]
+#[description = "Sets the units for the aviation commands, Like `ft`, `m`, `kt`, `kmh`, `mph`, `hpa`, `inhg`, `c` or `f`"]
+async fn units(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let mut database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+
+    // Check every unit before saving any of them
+    for unit in args.raw() {
+        if !database_user.units.set(unit) {
+            msg.channel_id.say(ctx, format!(":no_entry_sign: `{}` isn't a unit, Use ft, m, kt, kmh, mph, hpa, inhg, c or f.", unit)).await?;
+            return Ok(());
+        }
+    }
+
+    let units = database_user.units;
+    DatabaseUser::insert_or_replace(ctx, database_user).await;
+
+    msg.channel_id.say(ctx, format!(":white_check_mark: Set your units to {}", format_units(&units).replace('\n', ", "))).await?;
+
+    Ok(())
+}
+
+#[command]
+#[max_args(1)]
+#[aliases("base")]
+#[usage = "[ident]"]
+#[description = "Sets your home airport, The aviation commands use it when you leave out the ident"]
+async fn home(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let mut database_user = DatabaseUser::get_or_default(ctx, msg.author.id.0 as i64).await;
+
+    // Remove the home airport
+    if args.is_empty() {
+        database_user.home_airport = None;
+        DatabaseUser::insert_or_replace(ctx, database_user).await;
+
+        msg.channel_id.say(ctx, ":white_check_mark: Removed your home airport.").await?;
+        return Ok(());
+    }
+
+    let icao = args.single::<String>()?;
+
+    // Make sure the station exists before saving it, The same way the aviation commands look it up
+    let station = match resolve_station(ctx, &CommandSource::from(msg), &icao).await? {
+        Some(station) => station,
+        None => return Ok(()),
+    };
+
+    database_user.home_airport = Some(station.icao.clone());
+    DatabaseUser::insert_or_replace(ctx, database_user).await;
+
+    msg.channel_id.say(ctx, format!(":white_check_mark: Set your home airport to {} ({})", station.icao, station.name)).await?;
+
+    Ok(())
+}
+
+fn format_units(units: &UserUnits) -> String {
+    format!("Altitude: {}\nSpeed: {}\nPressure: {}\nTemperature: {}",
+            units.altitude_abbreviation(), units.speed_abbreviation(), units.pressure_abbreviation(), units.temperature_abbreviation())
+}
@@ -0,0 +1,272 @@
+// Slash command versions of the commands, They only read the options and run the same logic as the prefix commands.
+use std::env;
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::model::interactions::application_command::{ApplicationCommand, ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
+                                                          ApplicationCommandInteractionDataOptionValue, ApplicationCommandOptionType};
+use serenity::model::interactions::autocomplete::AutocompleteInteraction;
+use serenity::builder::CreateApplicationCommands;
+use serenity::framework::standard::CommandResult;
+use tracing::error;
+use crate::commands::meta::{run_ping, run_about, run_serverinfo, run_invite};
+use crate::commands::moderation::run_purge;
+use crate::commands::aviation::run_icao;
+use crate::commands::configuration::{run_prefix, list_counting_channels, add_counting_channel, remove_counting_channel, update_counting_settings, list_weather_alerts, add_weather_alert, remove_weather_alert};
+use crate::helpers::command_source::CommandSource;
+use crate::helpers::counting::CountingFailAction;
+use crate::helpers::counting_modes::CountingMode;
+use crate::helpers::global_data::AirportDatabaseContainer;
+
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+const MAX_CHOICE_NAME_LENGTH: usize = 100;
+
+/// Registers the slash commands with Discord.
+///
+/// Global commands can take an hour to show up, So they're registered on `SLASH_COMMANDS_GUILD` instead when it's set.
+pub async fn register_slash_commands(ctx: &Context) -> serenity::Result<()> {
+    match env::var("SLASH_COMMANDS_GUILD").ok().and_then(|guild_id| guild_id.parse::<u64>().ok()) {
+        Some(guild_id) => { GuildId::from(guild_id).set_application_commands(&ctx.http, create_commands).await?; }
+        None => { ApplicationCommand::set_global_application_commands(&ctx.http, create_commands).await?; }
+    }
+
+    Ok(())
+}
+
+fn create_commands(commands: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    commands
+        .create_application_command(|c| c.name("ping").description("Pong!"))
+        .create_application_command(|c| c.name("about").description("Some information about the bot."))
+        .create_application_command(|c| c.name("serverinfo").description("Gives information about a guild"))
+        .create_application_command(|c| c.name("invite").description("Gives you an invite link"))
+        .create_application_command(|c| c.name("purge").description("Deletes messages in this channel")
+            .create_option(|o| o.name("amount").description("How many messages to delete")
+                .kind(ApplicationCommandOptionType::Integer).required(true).min_int_value(1).max_int_value(100)))
+        .create_application_command(|c| c.name("icao").description("Shows information about an airport")
+            .create_option(|o| o.name("airport").description("Ident, IATA code, name or city, Your home airport when left out")
+                .kind(ApplicationCommandOptionType::String).set_autocomplete(true)))
+        .create_application_command(|c| c.name("config").description("Configures the bot for this server")
+            .create_option(|o| o.name("prefix").description("Sets the prefix for this server, Leave it out to reset it")
+                .kind(ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|o| o.name("prefix").description("The new prefix").kind(ApplicationCommandOptionType::String)))
+            .create_option(|o| o.name("count").description("Manages the counting channels")
+                .kind(ApplicationCommandOptionType::SubCommandGroup)
+                .create_sub_option(|o| o.name("list").description("Lists the counting channels").kind(ApplicationCommandOptionType::SubCommand))
+                .create_sub_option(|o| o.name("add").description("Adds a counting channel").kind(ApplicationCommandOptionType::SubCommand)
+                    .create_sub_option(|o| o.name("channel").description("The counting channel")
+                        .kind(ApplicationCommandOptionType::Channel).channel_types(&[ChannelType::Text]).required(true)))
+                .create_sub_option(|o| o.name("remove").description("Removes a counting channel").kind(ApplicationCommandOptionType::SubCommand)
+                    .create_sub_option(|o| o.name("channel").description("The counting channel")
+                        .kind(ApplicationCommandOptionType::Channel).channel_types(&[ChannelType::Text]).required(true)))
+                .create_sub_option(|o| o.name("rules").description("Sets the rules of a counting channel").kind(ApplicationCommandOptionType::SubCommand)
+                    .create_sub_option(|o| o.name("channel").description("The counting channel, This one or the only one when left out")
+                        .kind(ApplicationCommandOptionType::Channel).channel_types(&[ChannelType::Text]))
+                    .create_sub_option(|o| o.name("consecutive").description("Whether someone can count twice in a row").kind(ApplicationCommandOptionType::Boolean))
+                    .create_sub_option(|o| o.name("fail").description("What happens on a wrong count").kind(ApplicationCommandOptionType::String)
+                        .add_string_choice("Delete the message", "delete").add_string_choice("Reset the count", "reset")))
+                .create_sub_option(|o| o.name("mode").description("Sets how the numbers in a counting channel are written").kind(ApplicationCommandOptionType::SubCommand)
+                    .create_sub_option(|o| o.name("mode").description("The counting mode").kind(ApplicationCommandOptionType::String).required(true)
+                        .add_string_choice("Decimal", "decimal").add_string_choice("Math expressions", "math").add_string_choice("Hexadecimal", "hex")
+                        .add_string_choice("Binary", "binary").add_string_choice("Roman numerals", "roman"))
+                    .create_sub_option(|o| o.name("channel").description("The counting channel, This one or the only one when left out")
+                        .kind(ApplicationCommandOptionType::Channel).channel_types(&[ChannelType::Text]))))
+            .create_option(|o| o.name("wxalert").description("Posts in a channel when the weather at an airport changes")
+                .kind(ApplicationCommandOptionType::SubCommandGroup)
+                .create_sub_option(|o| o.name("list").description("Lists the weather alerts").kind(ApplicationCommandOptionType::SubCommand))
+                .create_sub_option(|o| o.name("add").description("Adds or replaces a weather alert").kind(ApplicationCommandOptionType::SubCommand)
+                    .create_sub_option(|o| o.name("channel").description("The channel to post in")
+                        .kind(ApplicationCommandOptionType::Channel).channel_types(&[ChannelType::Text]).required(true))
+                    .create_sub_option(|o| o.name("ident").description("The airport to watch")
+                        .kind(ApplicationCommandOptionType::String).set_autocomplete(true).required(true))
+                    .create_sub_option(|o| o.name("conditions").description("Like rules, wind:<kt> or gust:<kt>")
+                        .kind(ApplicationCommandOptionType::String).required(true)))
+                .create_sub_option(|o| o.name("remove").description("Removes a weather alert").kind(ApplicationCommandOptionType::SubCommand)
+                    .create_sub_option(|o| o.name("channel").description("The channel it posts in")
+                        .kind(ApplicationCommandOptionType::Channel).channel_types(&[ChannelType::Text]).required(true))
+                    .create_sub_option(|o| o.name("ident").description("The airport it watches")
+                        .kind(ApplicationCommandOptionType::String).set_autocomplete(true).required(true)))))
+}
+
+pub async fn handle_interaction(ctx: &Context, interaction: Interaction) {
+    match interaction {
+        Interaction::ApplicationCommand(command) => {
+            if let Err(why) = run_slash_command(ctx, &command).await {
+                error!("Error in /{}: {:?}", command.data.name, why);
+            }
+        }
+        Interaction::Autocomplete(autocomplete) => {
+            if let Err(why) = autocomplete_airport(ctx, &autocomplete).await {
+                error!("Error autocompleting /{}: {:?}", autocomplete.data.name, why);
+            }
+        }
+        _ => {}
+    }
+}
+
+async fn run_slash_command(ctx: &Context, command: &ApplicationCommandInteraction) -> CommandResult {
+    // The framework checks these for the prefix commands
+    let (guild_only, required_permissions) = match command.data.name.as_str() {
+        "serverinfo" => (true, Permissions::empty()),
+        "purge" => (true, Permissions::MANAGE_MESSAGES),
+        "config" => (true, Permissions::ADMINISTRATOR),
+        _ => (false, Permissions::empty()),
+    };
+    if guild_only && command.guild_id.is_none() {
+        return respond_ephemeral(ctx, command, ":no_entry_sign: This command is for guilds only.").await;
+    }
+    let permissions = command.member.as_ref().and_then(|member| member.permissions).unwrap_or_else(Permissions::empty);
+    if !permissions.administrator() && !permissions.contains(required_permissions) {
+        return respond_ephemeral(ctx, command, format!(":no_entry_sign: You're lacking these permissions to run this command: `{}`", required_permissions)).await;
+    }
+
+    // Discord only waits three seconds for an answer, Most commands take longer
+    command.defer(&ctx.http).await?;
+
+    // The deferred answer keeps thinking until something is sent, So a failed command still has to answer
+    let source = CommandSource::from(command);
+    if let Err(why) = run_deferred_command(ctx, &source, command).await {
+        source.say(ctx, ":no_entry_sign: Something went wrong running this command.").await?;
+        return Err(why);
+    }
+
+    Ok(())
+}
+
+async fn run_deferred_command(ctx: &Context, source: &CommandSource<'_>, command: &ApplicationCommandInteraction) -> CommandResult {
+    let options = &command.data.options;
+    match command.data.name.as_str() {
+        "ping" => run_ping(ctx, source).await,
+        "about" => run_about(ctx, source).await,
+        "serverinfo" => run_serverinfo(ctx, source).await,
+        "invite" => run_invite(ctx, source).await,
+        "purge" => run_purge(ctx, source, integer_option(options, "amount").unwrap_or(1) as u64).await,
+        "icao" => run_icao(ctx, source, string_option(options, "airport")).await,
+        "config" => run_config(ctx, source, options).await,
+        _ => {
+            source.say(ctx, ":no_entry_sign: This command doesn't exist anymore.").await?;
+            Ok(())
+        }
+    }
+}
+
+// Discord checks the options against the registered ones, So the fallbacks only answer for ones that changed since
+async fn run_config(ctx: &Context, source: &CommandSource<'_>, options: &[ApplicationCommandInteractionDataOption]) -> CommandResult {
+    let subcommand = match options.first() {
+        Some(subcommand) => subcommand,
+        None => return unknown_option(ctx, source).await,
+    };
+
+    match subcommand.name.as_str() {
+        "prefix" => run_prefix(ctx, source, string_option(&subcommand.options, "prefix")).await,
+        "count" => {
+            let action = match subcommand.options.first() {
+                Some(action) => action,
+                None => return unknown_option(ctx, source).await,
+            };
+            let channel = channel_option(&action.options, "channel");
+            match (action.name.as_str(), channel) {
+                ("list", _) => list_counting_channels(ctx, source).await,
+                ("add", Some(channel)) => add_counting_channel(ctx, source, channel).await,
+                ("remove", Some(channel)) => remove_counting_channel(ctx, source, channel).await,
+                ("rules", _) => {
+                    let on_fail = string_option(&action.options, "fail").and_then(|on_fail| CountingFailAction::parse(&on_fail));
+                    update_counting_settings(ctx, source, channel, boolean_option(&action.options, "consecutive"), on_fail, None).await
+                }
+                ("mode", _) => {
+                    let mode = string_option(&action.options, "mode").and_then(|mode| CountingMode::parse(&mode));
+                    update_counting_settings(ctx, source, channel, None, None, mode).await
+                }
+                _ => unknown_option(ctx, source).await,
+            }
+        }
+        "wxalert" => {
+            let action = match subcommand.options.first() {
+                Some(action) => action,
+                None => return unknown_option(ctx, source).await,
+            };
+            let (channel, ident) = (channel_option(&action.options, "channel"), string_option(&action.options, "ident"));
+            match (action.name.as_str(), channel, ident, string_option(&action.options, "conditions")) {
+                ("list", ..) => list_weather_alerts(ctx, source).await,
+                ("add", Some(channel), Some(ident), Some(conditions)) => add_weather_alert(ctx, source, channel, ident, &conditions).await,
+                ("remove", Some(channel), Some(ident), _) => remove_weather_alert(ctx, source, channel, ident).await,
+                _ => unknown_option(ctx, source).await,
+            }
+        }
+        _ => unknown_option(ctx, source).await,
+    }
+}
+
+async fn unknown_option(ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
+    source.say(ctx, ":no_entry_sign: Those options aren't known, Try the command again.").await?;
+
+    Ok(())
+}
+
+// Answers before deferring, Only the user sees it
+async fn respond_ephemeral(ctx: &Context, command: &ApplicationCommandInteraction, content: impl ToString) -> CommandResult {
+    command.create_interaction_response(&ctx.http, |r| r
+        .kind(InteractionResponseType::ChannelMessageWithSource)
+        .interaction_response_data(|d| d.content(content).flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL))
+    ).await?;
+
+    Ok(())
+}
+
+// Suggests airports from the local airport database for whichever airport option is being typed
+async fn autocomplete_airport(ctx: &Context, autocomplete: &AutocompleteInteraction) -> serenity::Result<()> {
+    let partial = match focused_option(&autocomplete.data.options).and_then(|option| option.value.as_ref()).and_then(|value| value.as_str()) {
+        Some(partial) => partial.to_string(),
+        None => return Ok(()),
+    };
+
+    let choices = {
+        let airport_database = ctx.data.read().await.get::<AirportDatabaseContainer>().cloned().unwrap();
+        let airport_database = airport_database.read().await;
+        airport_database.complete(&partial, MAX_AUTOCOMPLETE_CHOICES).into_iter()
+            .map(|airport| (format!("{} - {}", airport.icao, airport.name).chars().take(MAX_CHOICE_NAME_LENGTH).collect::<String>(), airport.icao.clone()))
+            .collect::<Vec<(String, String)>>()
+    };
+
+    autocomplete.create_autocomplete_response(&ctx.http, |response| {
+        for (name, value) in choices {
+            response.add_string_choice(name, value);
+        }
+        response
+    }).await
+}
+
+// The option being typed in, Which can be inside a subcommand
+fn focused_option(options: &[ApplicationCommandInteractionDataOption]) -> Option<&ApplicationCommandInteractionDataOption> {
+    options.iter().find_map(|option| if option.focused { Some(option) } else { focused_option(&option.options) })
+}
+
+fn resolved_option<'a>(options: &'a [ApplicationCommandInteractionDataOption], name: &str) -> Option<&'a ApplicationCommandInteractionDataOptionValue> {
+    options.iter().find(|option| option.name == name)?.resolved.as_ref()
+}
+
+fn string_option(options: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<String> {
+    match resolved_option(options, name)? {
+        ApplicationCommandInteractionDataOptionValue::String(value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn integer_option(options: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<i64> {
+    match resolved_option(options, name)? {
+        ApplicationCommandInteractionDataOptionValue::Integer(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn boolean_option(options: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<bool> {
+    match resolved_option(options, name)? {
+        ApplicationCommandInteractionDataOptionValue::Boolean(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn channel_option(options: &[ApplicationCommandInteractionDataOption], name: &str) -> Option<ChannelId> {
+    match resolved_option(options, name)? {
+        ApplicationCommandInteractionDataOptionValue::Channel(channel) => Some(channel.id),
+        _ => None,
+    }
+}
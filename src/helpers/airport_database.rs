@@ -0,0 +1,295 @@
+// The OurAirports dataset kept in memory, So station lookups and the icao menu don't need AVWX.
+use std::{collections::HashMap, env, fmt, fs, io, path::{Path, PathBuf}};
+use serde::de::DeserializeOwned;
+use strsim::jaro_winkler;
+use tracing::{info, warn};
+use crate::helpers::aviation_helper::{great_circle, GreatCircle};
+use crate::models::avwx::{AvwxIcao, AvwxIcaoRunway};
+use crate::models::ourairports::{OurAirportsAirport, OurAirportsRunway, OurAirportsFrequency, OurAirportsNavaid};
+
+pub const AIRPORTS_FILE: &str = "airports.csv";
+pub const RUNWAYS_FILE: &str = "runways.csv";
+pub const FREQUENCIES_FILE: &str = "airport-frequencies.csv";
+pub const NAVAIDS_FILE: &str = "navaids.csv";
+// Older dumps and some mirrors name the frequencies file like this
+const FREQUENCIES_FILE_ALIAS: &str = "frequencies.csv";
+
+const NEARBY_NAVAID_NM: f64 = 40.0;
+const MAX_NEARBY_NAVAIDS: usize = 10;
+const MAX_SEARCH_RESULTS: usize = 9;
+// Shorter partial codes are only completed by code, Fuzzy matching a letter or two finds everything
+const MIN_FUZZY_COMPLETION_LENGTH: usize = 3;
+const MINIMUM_SEARCH_SCORE: f64 = 0.85;
+const AIRPORT_TYPE_PENALTY: f64 = 0.03;
+
+#[derive(Default)]
+pub struct AirportDatabase {
+    airports: HashMap<String, AvwxIcao>,
+    // IATA and GPS codes pointing to the ident of the airport
+    aliases: HashMap<String, String>,
+    frequencies: HashMap<String, Vec<OurAirportsFrequency>>,
+    navaids: Vec<OurAirportsNavaid>,
+    search_entries: Vec<SearchEntry>,
+}
+
+// The names of an airport lowercased once, So searching doesn't allocate for every airport
+struct SearchEntry {
+    ident: String,
+    name: String,
+    city: String,
+    // The first letter of every word in the name and city
+    initials: String,
+}
+
+impl SearchEntry {
+    fn new(airport: &AvwxIcao) -> SearchEntry {
+        let (name, city) = (airport.name.to_lowercase(), airport.city.to_lowercase());
+        let initials = words(&name).chain(words(&city)).filter_map(|word| word.chars().next()).collect();
+
+        SearchEntry { ident: airport.icao.clone(), name, city, initials }
+    }
+}
+
+#[derive(Debug)]
+pub enum AirportDatabaseError {
+    NotConfigured,
+    Io(PathBuf, io::Error),
+    Csv(PathBuf, csv::Error),
+}
+
+impl fmt::Display for AirportDatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AirportDatabaseError::NotConfigured => write!(f, "AIRPORT_DATA_DIR isn't set"),
+            AirportDatabaseError::Io(path, why) => write!(f, "Couldn't copy {}: {}", path.display(), why),
+            AirportDatabaseError::Csv(path, why) => write!(f, "Couldn't read {}: {}", path.display(), why),
+        }
+    }
+}
+
+impl std::error::Error for AirportDatabaseError {}
+
+impl AirportDatabase {
+    /// Reads the CSV files in `AIRPORT_DATA_DIR`.
+    pub async fn load_from_env() -> Result<AirportDatabase, AirportDatabaseError> {
+        let directory = env::var("AIRPORT_DATA_DIR").map_err(|_| AirportDatabaseError::NotConfigured)?;
+
+        AirportDatabase::load(PathBuf::from(directory)).await
+    }
+
+    /// Reads the CSV files in a directory, On a blocking thread since the dumps are big.
+    pub async fn load(directory: PathBuf) -> Result<AirportDatabase, AirportDatabaseError> {
+        tokio::task::spawn_blocking(move || AirportDatabase::read(&directory)).await.unwrap()
+    }
+
+    /// Copies the CSV files from a directory into `AIRPORT_DATA_DIR` so they're loaded on the next start, Then reads them.
+    ///
+    /// Without `AIRPORT_DATA_DIR` the files are only read.
+    pub async fn import(source: PathBuf) -> Result<AirportDatabase, AirportDatabaseError> {
+        let directory = match env::var("AIRPORT_DATA_DIR") {
+            Ok(directory) => PathBuf::from(directory),
+            Err(_) => return AirportDatabase::load(source).await,
+        };
+
+        tokio::task::spawn_blocking(move || {
+            fs::create_dir_all(&directory).map_err(|why| AirportDatabaseError::Io(directory.clone(), why))?;
+            for file_name in &[AIRPORTS_FILE, RUNWAYS_FILE, FREQUENCIES_FILE, FREQUENCIES_FILE_ALIAS, NAVAIDS_FILE] {
+                let source_file = source.join(file_name);
+                let destination_file = directory.join(file_name);
+                if source_file.exists() && source_file != destination_file {
+                    fs::copy(&source_file, &destination_file).map_err(|why| AirportDatabaseError::Io(source_file.clone(), why))?;
+                }
+            }
+
+            AirportDatabase::read(&directory)
+        }).await.unwrap()
+    }
+
+    fn read(directory: &Path) -> Result<AirportDatabase, AirportDatabaseError> {
+        let mut runways: HashMap<String, Vec<AvwxIcaoRunway>> = HashMap::new();
+        for runway in read_csv::<OurAirportsRunway>(&directory.join(RUNWAYS_FILE))? {
+            if runway.closed == 0 {
+                runways.entry(runway.airport_ident.to_uppercase()).or_default().push(AvwxIcaoRunway::from(runway));
+            }
+        }
+
+        let mut airports = HashMap::new();
+        let mut aliases = HashMap::new();
+        for airport in read_csv::<OurAirportsAirport>(&directory.join(AIRPORTS_FILE))? {
+            let ident = airport.ident.to_uppercase();
+            for alias in airport.iata_code.iter().chain(airport.gps_code.iter()) {
+                if !alias.is_empty() && !alias.eq_ignore_ascii_case(&ident) {
+                    aliases.insert(alias.to_uppercase(), ident.clone());
+                }
+            }
+            let airport_runways = runways.remove(&ident).unwrap_or_default();
+            airports.insert(ident, airport.into_station(airport_runways));
+        }
+
+        let frequencies_path = if directory.join(FREQUENCIES_FILE).exists() {
+            directory.join(FREQUENCIES_FILE)
+        } else {
+            directory.join(FREQUENCIES_FILE_ALIAS)
+        };
+        let mut frequencies: HashMap<String, Vec<OurAirportsFrequency>> = HashMap::new();
+        for frequency in read_csv::<OurAirportsFrequency>(&frequencies_path)? {
+            frequencies.entry(frequency.airport_ident.to_uppercase()).or_default().push(frequency);
+        }
+
+        let search_entries = airports.values().map(SearchEntry::new).collect();
+        Ok(AirportDatabase {
+            airports,
+            aliases,
+            frequencies,
+            navaids: read_csv(&directory.join(NAVAIDS_FILE))?,
+            search_entries,
+        })
+    }
+
+    /// Finds an airport by its ident, IATA code or GPS code.
+    pub fn station(&self, code: &str) -> Option<&AvwxIcao> {
+        let code = code.to_uppercase();
+
+        self.airports.get(&code)
+            .or_else(|| self.aliases.get(&code).and_then(|ident| self.airports.get(ident)))
+    }
+
+    /// Airports ranked by how well their code, Name or city matches the query, Best first.
+    ///
+    /// An exact ident, IATA or GPS code is the only result.
+    pub fn search(&self, query: &str) -> Vec<(&AvwxIcao, f64)> {
+        if let Some(station) = self.station(query) {
+            return vec![(station, 1.0)];
+        }
+
+        let query = query.trim().to_lowercase();
+        let first_letter = match query.chars().next() {
+            Some(first_letter) => first_letter,
+            None => return Vec::new(),
+        };
+        // Typos are hardly ever in the first letter, So only airports with a word starting like the query are scored
+        let mut results = self.search_entries.iter()
+            .filter(|search_entry| search_entry.initials.contains(first_letter))
+            .filter_map(|search_entry| {
+                let airport = self.airports.get(&search_entry.ident)?;
+                let score = match_score(&query, &search_entry.name).max(match_score(&query, &search_entry.city));
+                // Bigger airports are more likely what people mean
+                Some((airport, score - airport_type_rank(&airport.airport_type) as f64 * AIRPORT_TYPE_PENALTY))
+            })
+            .filter(|(_, score)| *score >= MINIMUM_SEARCH_SCORE)
+            .collect::<Vec<(&AvwxIcao, f64)>>();
+        results.sort_by(|a, b| b.1.total_cmp(&a.1));
+        results.truncate(MAX_SEARCH_RESULTS);
+
+        results
+    }
+
+    /// Airports for autocompleting a partly typed code, Codes that start with it first and then the search results.
+    pub fn complete(&self, partial: &str, limit: usize) -> Vec<&AvwxIcao> {
+        let partial = partial.trim().to_uppercase();
+        if partial.is_empty() {
+            return Vec::new();
+        }
+
+        let mut completions = self.airports.values()
+            .filter(|airport| airport.icao.starts_with(&partial) || airport.iata.starts_with(&partial))
+            .collect::<Vec<&AvwxIcao>>();
+        completions.sort_by(|a, b| airport_type_rank(&a.airport_type).cmp(&airport_type_rank(&b.airport_type)).then(a.icao.cmp(&b.icao)));
+        completions.truncate(limit);
+
+        if partial.len() < MIN_FUZZY_COMPLETION_LENGTH {
+            return completions;
+        }
+        for (airport, _) in self.search(&partial) {
+            if completions.len() < limit && !completions.iter().any(|completion| completion.icao == airport.icao) {
+                completions.push(airport);
+            }
+        }
+
+        completions
+    }
+
+    pub fn frequencies(&self, ident: &str) -> &[OurAirportsFrequency] {
+        self.frequencies.get(&ident.to_uppercase()).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Navaids that belong to the airport or are close to it, Closest first.
+    pub fn nearby_navaids(&self, ident: &str, latitude: f64, longitude: f64) -> Vec<(&OurAirportsNavaid, GreatCircle)> {
+        let mut nearby_navaids = self.navaids.iter()
+            .map(|navaid| (navaid, great_circle((latitude, longitude), (navaid.latitude_deg, navaid.longitude_deg))))
+            .filter(|(navaid, route)| {
+                route.distance_nm <= NEARBY_NAVAID_NM
+                    || navaid.associated_airport.as_deref().map(|airport| airport.eq_ignore_ascii_case(ident)).unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        nearby_navaids.sort_by(|a, b| a.1.distance_nm.total_cmp(&b.1.distance_nm));
+        nearby_navaids.truncate(MAX_NEARBY_NAVAIDS);
+
+        nearby_navaids
+    }
+
+    pub fn airport_count(&self) -> usize {
+        self.airports.len()
+    }
+
+    pub fn frequency_count(&self) -> usize {
+        self.frequencies.values().map(Vec::len).sum()
+    }
+
+    pub fn navaid_count(&self) -> usize {
+        self.navaids.len()
+    }
+}
+
+fn airport_type_rank(airport_type: &str) -> u8 {
+    match airport_type {
+        "large_airport" => 0,
+        "medium_airport" => 1,
+        "small_airport" => 2,
+        _ => 3,
+    }
+}
+
+// The best Jaro-Winkler similarity of the query to the whole text or one of its words, Both lowercase
+fn match_score(query: &str, text: &str) -> f64 {
+    if text.is_empty() {
+        return 0.0;
+    } else if text == query {
+        return 1.0;
+    }
+
+    let word_score = words(text)
+        .map(|word| jaro_winkler(query, word))
+        .fold(0.0, f64::max);
+    // Queries of several words like `los angeles` are still found inside longer names
+    let contains_score = if query.contains(' ') && text.contains(query) { 0.95 } else { 0.0 };
+
+    jaro_winkler(query, text).max(word_score).max(contains_score)
+}
+
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|character: char| !character.is_alphanumeric()).filter(|word| !word.is_empty())
+}
+
+// A missing file is the same as an empty one, Rows that don't match the columns are skipped since the dumps have a few of those
+fn read_csv<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, AirportDatabaseError> {
+    if !path.exists() {
+        info!("{} doesn't exist, Skipping it", path.display());
+        return Ok(Vec::new());
+    }
+    let mut reader = csv::Reader::from_path(path).map_err(|why| AirportDatabaseError::Csv(path.to_path_buf(), why))?;
+
+    let mut rows = Vec::new();
+    let mut skipped = 0;
+    for row in reader.deserialize::<T>() {
+        match row {
+            Ok(row) => rows.push(row),
+            Err(_) => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        warn!("Skipped {} rows of {} that couldn't be read", skipped, path.display());
+    }
+
+    Ok(rows)
+}
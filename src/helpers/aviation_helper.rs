@@ -0,0 +1,393 @@
+// Calculations for the aviation commands, Nothing in here touches the network.
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use crate::models::avwx::AvwxIcao;
+use crate::models::metar::Altimeter;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindComponents {
+    /// Negative when it's a tailwind.
+    pub headwind: f32,
+    /// Negative when the wind comes from the left.
+    pub crosswind: f32,
+}
+
+/// Splits the wind into head and crosswind components for a runway heading, All in degrees true.
+pub fn wind_components(runway_heading: f32, wind_direction: f32, wind_speed: f32) -> WindComponents {
+    let angle = (wind_direction - runway_heading).to_radians();
+
+    WindComponents {
+        headwind: wind_speed * angle.cos(),
+        crosswind: wind_speed * angle.sin(),
+    }
+}
+
+const EARTH_RADIUS_NM: f64 = 3440.065;
+pub const KILOMETERS_PER_NAUTICAL_MILE: f64 = 1.852;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GreatCircle {
+    pub distance_nm: f64,
+    /// True bearing when leaving the first point.
+    pub initial_bearing: f64,
+    /// True bearing when arriving at the second point.
+    pub final_bearing: f64,
+}
+
+/// Distance and bearings along the great circle between two latitude and longitude pairs in degrees.
+pub fn great_circle(from: (f64, f64), to: (f64, f64)) -> GreatCircle {
+    let (from_latitude, from_longitude) = (from.0.to_radians(), from.1.to_radians());
+    let (to_latitude, to_longitude) = (to.0.to_radians(), to.1.to_radians());
+
+    // Haversine formula
+    let delta_latitude = to_latitude - from_latitude;
+    let delta_longitude = to_longitude - from_longitude;
+    let a = (delta_latitude / 2.0).sin().powi(2) + from_latitude.cos() * to_latitude.cos() * (delta_longitude / 2.0).sin().powi(2);
+    let distance_nm = 2.0 * EARTH_RADIUS_NM * a.sqrt().atan2((1.0 - a).sqrt());
+
+    // The final bearing is the reverse of the bearing from the destination back
+    GreatCircle {
+        distance_nm,
+        initial_bearing: initial_bearing(from_latitude, from_longitude, to_latitude, to_longitude),
+        final_bearing: (initial_bearing(to_latitude, to_longitude, from_latitude, from_longitude) + 180.0) % 360.0,
+    }
+}
+
+// Expects radians, Returns degrees
+fn initial_bearing(from_latitude: f64, from_longitude: f64, to_latitude: f64, to_longitude: f64) -> f64 {
+    let delta_longitude = to_longitude - from_longitude;
+    let y = delta_longitude.sin() * to_latitude.cos();
+    let x = from_latitude.cos() * to_latitude.sin() - from_latitude.sin() * to_latitude.cos() * delta_longitude.cos();
+
+    (y.atan2(x).to_degrees() + 360.0) % 360.0
+}
+
+/// Formats the time it takes to fly a distance as hours and minutes.
+pub fn format_enroute_time(distance_nm: f64, groundspeed_kt: f64) -> String {
+    let minutes = (distance_nm / groundspeed_kt * 60.0).round() as u64;
+
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Filters for station searches, Parsed from arguments like `type:small_airport runway:1500m surface:paved reporting`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StationFilter {
+    pub airport_type: Option<String>,
+    pub minimum_runway_ft: Option<i32>,
+    pub surface: Option<String>,
+    pub reporting: bool,
+}
+
+impl StationFilter {
+    pub fn parse(arguments: &str) -> Result<StationFilter, String> {
+        let mut filter = StationFilter::default();
+
+        for argument in arguments.split_whitespace().map(|argument| argument.to_lowercase()) {
+            let (key, value) = argument.split_once(':').unwrap_or((argument.as_str(), ""));
+            match key {
+                "type" => filter.airport_type = Some(value.to_string()),
+                "runway" | "length" => {
+                    // Feet unless it ends in meters
+                    let length = if let Some(meters) = value.strip_suffix('m') {
+                        meters.parse::<f32>().map(|meters| (meters / 0.3048).round() as i32)
+                    } else {
+                        value.trim_end_matches("ft").parse::<f32>().map(|feet| feet.round() as i32)
+                    };
+                    filter.minimum_runway_ft = Some(length.map_err(|_| format!("`{}` isn't a valid runway length.", value))?);
+                }
+                "surface" => filter.surface = Some(value.to_string()),
+                "reporting" | "weather" => filter.reporting = true,
+                _ => return Err(format!("`{}` isn't a filter, Use type, runway, surface or reporting.", argument)),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    pub fn matches(&self, avwx_icao: &AvwxIcao) -> bool {
+        if self.reporting && !avwx_icao.reporting {
+            return false;
+        }
+        if let Some(airport_type) = &self.airport_type {
+            if !avwx_icao.airport_type.to_lowercase().contains(airport_type.as_str()) {
+                return false;
+            }
+        }
+
+        // One runway has to meet both the length and the surface
+        if self.minimum_runway_ft.is_some() || self.surface.is_some() {
+            return avwx_icao.runways.iter().any(|runway| {
+                self.minimum_runway_ft.map(|minimum| runway.length_ft >= minimum).unwrap_or(true)
+                    && self.surface.as_ref().map(|surface| surface_matches(&runway.surface, surface)).unwrap_or(true)
+            });
+        }
+
+        true
+    }
+}
+
+// `paved` and `unpaved` group the surface codes that stations use
+fn surface_matches(runway_surface: &str, wanted_surface: &str) -> bool {
+    let runway_surface = runway_surface.to_lowercase();
+    let is_paved = ["asp", "con", "bit", "pav", "tar", "pem"].iter().any(|paved| runway_surface.contains(paved));
+
+    match wanted_surface {
+        "paved" => is_paved,
+        "unpaved" => !is_paved,
+        _ => runway_surface.contains(wanted_surface),
+    }
+}
+
+/// Links to the published charts of an airport, Sites that only cover its country come first.
+pub fn chart_links(ident: &str, country: &str) -> Vec<(&'static str, String)> {
+    let mut links = Vec::new();
+    if country.eq_ignore_ascii_case("US") {
+        links.push(("AirNav", format!("https://www.airnav.com/airport/{}", ident)));
+    }
+    links.push(("ChartFox", format!("https://chartfox.org/{}", ident)));
+    links.push(("SkyVector", format!("https://skyvector.com/airport/{}", ident)));
+
+    links
+}
+
+const STANDARD_PRESSURE_INHG: f32 = 29.92;
+
+/// The altitude the altimeter shows when set to 29.92 inHg, 1000 ft for every inch below standard.
+pub fn pressure_altitude(elevation_ft: f32, altimeter: Altimeter) -> f32 {
+    elevation_ft + (STANDARD_PRESSURE_INHG - altimeter.as_inches_of_mercury()) * 1000.0
+}
+
+/// The ISA temperature at a pressure altitude, 15°C at sea level dropping 2°C every 1000 ft.
+pub fn isa_temperature(pressure_altitude_ft: f32) -> f32 {
+    15.0 - 2.0 * pressure_altitude_ft / 1000.0
+}
+
+/// The altitude the aircraft performs like, 120 ft for every °C above ISA.
+pub fn density_altitude(pressure_altitude_ft: f32, temperature_c: f32) -> f32 {
+    pressure_altitude_ft + 120.0 * (temperature_c - isa_temperature(pressure_altitude_ft))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DensityAltitudeBand {
+    Normal,
+    Caution,
+    Warning,
+    Danger,
+}
+
+impl DensityAltitudeBand {
+    pub fn from_density_altitude(density_altitude_ft: f32) -> DensityAltitudeBand {
+        match density_altitude_ft {
+            altitude if altitude < 3000.0 => DensityAltitudeBand::Normal,
+            altitude if altitude < 5000.0 => DensityAltitudeBand::Caution,
+            altitude if altitude < 8000.0 => DensityAltitudeBand::Warning,
+            _ => DensityAltitudeBand::Danger,
+        }
+    }
+
+    pub fn advice(self) -> &'static str {
+        match self {
+            DensityAltitudeBand::Normal => "Performance should be close to the book.",
+            DensityAltitudeBand::Caution => "Expect a longer takeoff roll and a lower climb rate.",
+            DensityAltitudeBand::Warning => "Performance is noticeably reduced, Check the takeoff and climb charts.",
+            DensityAltitudeBand::Danger => "Performance is severely reduced, Many light aircraft can't climb safely.",
+        }
+    }
+}
+
+/// Reads an elevation like `5000ft` or `1500m` as feet.
+pub fn parse_elevation(argument: &str) -> Option<f32> {
+    let argument = argument.to_lowercase();
+    if let Some(feet) = argument.strip_suffix("ft") {
+        feet.parse::<f32>().ok()
+    } else {
+        argument.strip_suffix('m')?.parse::<f32>().ok().map(|meters| meters / 0.3048)
+    }
+}
+
+/// Reads a temperature like `32C`, `-5c` or `90F` as °C.
+pub fn parse_temperature(argument: &str) -> Option<f32> {
+    let argument = argument.to_lowercase();
+    let argument = argument.replace('°', "");
+    if let Some(celsius) = argument.strip_suffix('c') {
+        celsius.parse::<f32>().ok()
+    } else {
+        argument.strip_suffix('f')?.parse::<f32>().ok().map(|fahrenheit| (fahrenheit - 32.0) * 5.0 / 9.0)
+    }
+}
+
+/// Reads an altimeter setting like `29.80`, `1013`, `29.80inHg` or `1013hPa`.
+///
+/// Plain numbers above 100 are taken as hPa.
+pub fn parse_altimeter(argument: &str) -> Option<Altimeter> {
+    let argument = argument.to_lowercase();
+    if let Some(inches) = argument.strip_suffix("inhg") {
+        inches.parse::<f32>().ok().map(Altimeter::InchesOfMercury)
+    } else if let Some(hectopascals) = argument.strip_suffix("hpa") {
+        hectopascals.parse::<f32>().ok().map(Altimeter::Hectopascals)
+    } else {
+        argument.parse::<f32>().ok().map(|value| if value > 100.0 { Altimeter::Hectopascals(value) } else { Altimeter::InchesOfMercury(value) })
+    }
+}
+
+// Where the centre of the sun is at sunrise and sunset with refraction, And at the end of civil twilight
+const SUNRISE_ALTITUDE: f64 = -0.833;
+const CIVIL_TWILIGHT_ALTITUDE: f64 = -6.0;
+const EARTH_AXIAL_TILT: f64 = 23.4397;
+const J2000_JULIAN_DAY: f64 = 2451545.0;
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2440587.5;
+// Night is checked this many minutes apart, And to the minute in between when it changes
+const NIGHT_SAMPLE_MINUTES: i64 = 10;
+
+/// When the sun crosses an altitude on a day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SunCrossing {
+    Times(DateTime<Utc>, DateTime<Utc>),
+    /// The sun stays above the altitude all day, Like the midnight sun.
+    AlwaysAbove,
+    /// The sun stays below the altitude all day, Like the polar night.
+    AlwaysBelow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DaylightTimes {
+    pub solar_noon: DateTime<Utc>,
+    /// Sunrise and sunset.
+    pub sun: SunCrossing,
+    /// Civil dawn and civil dusk.
+    pub civil_twilight: SunCrossing,
+}
+
+/// Works out sunrise, Sunset and civil twilight with the sunrise equation, Accurate to about a minute.
+pub fn daylight_times(latitude: f64, longitude: f64, date: NaiveDate) -> DaylightTimes {
+    // Days since J2000 at noon UTC, Shifted to the local solar noon
+    let noon = Utc.from_utc_datetime(&date.and_hms_opt(12, 0, 0).unwrap());
+    let days = (julian_day(noon) - J2000_JULIAN_DAY + 0.0008).round();
+    let mean_solar_time = days - longitude / 360.0;
+
+    // Where the sun is on its orbit
+    let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0).to_radians();
+    let equation_of_center = 1.9148 * mean_anomaly.sin() + 0.02 * (2.0 * mean_anomaly).sin() + 0.0003 * (3.0 * mean_anomaly).sin();
+    let ecliptic_longitude = (mean_anomaly.to_degrees() + equation_of_center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    let solar_transit = J2000_JULIAN_DAY + mean_solar_time + 0.0053 * mean_anomaly.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+    let declination = (ecliptic_longitude.sin() * EARTH_AXIAL_TILT.to_radians().sin()).asin();
+
+    let crossing = |altitude: f64| {
+        let latitude = latitude.to_radians();
+        let cos_hour_angle = (altitude.to_radians().sin() - latitude.sin() * declination.sin()) / (latitude.cos() * declination.cos());
+        if cos_hour_angle > 1.0 {
+            SunCrossing::AlwaysBelow
+        } else if cos_hour_angle < -1.0 {
+            SunCrossing::AlwaysAbove
+        } else {
+            let hour_angle = cos_hour_angle.acos().to_degrees();
+            SunCrossing::Times(from_julian_day(solar_transit - hour_angle / 360.0), from_julian_day(solar_transit + hour_angle / 360.0))
+        }
+    };
+
+    DaylightTimes {
+        solar_noon: from_julian_day(solar_transit),
+        sun: crossing(SUNRISE_ALTITUDE),
+        civil_twilight: crossing(CIVIL_TWILIGHT_ALTITUDE),
+    }
+}
+
+/// Whether it's night at a location, Which for logging is between the end and start of civil twilight.
+pub fn is_night(latitude: f64, longitude: f64, time: DateTime<Utc>) -> bool {
+    // The solar day of a date can start on the day before in UTC, So look at the days around it too
+    let is_day = (-1..=1).any(|offset| {
+        let daylight = daylight_times(latitude, longitude, time.date_naive() + Duration::days(offset));
+        match daylight.civil_twilight {
+            SunCrossing::Times(dawn, dusk) => dawn <= time && time < dusk,
+            SunCrossing::AlwaysAbove => (time - daylight.solar_noon).num_minutes().abs() <= 12 * 60,
+            SunCrossing::AlwaysBelow => false,
+        }
+    });
+
+    !is_day
+}
+
+/// Minutes of a flight flown at night, Assuming a straight line between the airports at a steady speed.
+pub fn night_minutes(from: (f64, f64), to: (f64, f64), departed_at: DateTime<Utc>, duration_minutes: i64) -> i64 {
+    // Checks halfway through a minute of the flight
+    let is_night_at = |minute: i64| {
+        let progress = (minute as f64 + 0.5) / duration_minutes as f64;
+        let latitude = from.0 + (to.0 - from.0) * progress;
+        let longitude = from.1 + (to.1 - from.1) * progress;
+        is_night(latitude, longitude, departed_at + Duration::seconds(minute * 60 + 30))
+    };
+
+    // Day and night only change once between two samples, So a sample only has to be split when its ends differ
+    let mut night_minutes = 0;
+    let mut start = 0;
+    while start < duration_minutes {
+        let end = (start + NIGHT_SAMPLE_MINUTES).min(duration_minutes);
+        let (start_is_night, end_is_night) = (is_night_at(start), is_night_at(end - 1));
+        if start_is_night == end_is_night {
+            if start_is_night {
+                night_minutes += end - start;
+            }
+        } else {
+            // The first minute that's like the end
+            let (mut low, mut high) = (start, end - 1);
+            while high - low > 1 {
+                let middle = (low + high) / 2;
+                if is_night_at(middle) == start_is_night { low = middle; } else { high = middle; }
+            }
+            night_minutes += if start_is_night { high - start } else { end - high };
+        }
+
+        start = end;
+    }
+
+    night_minutes
+}
+
+fn julian_day(time: DateTime<Utc>) -> f64 {
+    time.timestamp() as f64 / 86400.0 + UNIX_EPOCH_JULIAN_DAY
+}
+
+fn from_julian_day(julian_day: f64) -> DateTime<Utc> {
+    Utc.timestamp_opt(((julian_day - UNIX_EPOCH_JULIAN_DAY) * 86400.0).round() as i64, 0).unwrap()
+}
+
+/// The time zone at a location, None out at sea.
+pub fn local_time_zone(latitude: f64, longitude: f64) -> Option<Tz> {
+    // The lookup panics outside of these
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+
+    tz_search::lookup(latitude, longitude)?.parse::<Tz>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every minute on its own, Like night_minutes worked before
+    fn night_minutes_per_minute(from: (f64, f64), to: (f64, f64), departed_at: DateTime<Utc>, duration_minutes: i64) -> i64 {
+        (0..duration_minutes)
+            .filter(|minute| {
+                let progress = (*minute as f64 + 0.5) / duration_minutes as f64;
+                is_night(from.0 + (to.0 - from.0) * progress, from.1 + (to.1 - from.1) * progress, departed_at + Duration::seconds(minute * 60 + 30))
+            })
+            .count() as i64
+    }
+
+    #[test]
+    fn night_minutes_match_checking_every_minute() {
+        let (amsterdam, new_york, singapore) = ((52.31, 4.76), (40.64, -73.78), (1.36, 103.99));
+        let flights = [
+            (amsterdam, new_york, Utc.with_ymd_and_hms(2021, 12, 21, 13, 0, 0).unwrap(), 8 * 60 + 17),
+            (new_york, amsterdam, Utc.with_ymd_and_hms(2021, 6, 21, 22, 0, 0).unwrap(), 7 * 60 + 3),
+            (singapore, amsterdam, Utc.with_ymd_and_hms(2021, 3, 1, 15, 30, 0).unwrap(), 13 * 60 + 1),
+            (amsterdam, amsterdam, Utc.with_ymd_and_hms(2021, 9, 1, 0, 0, 0).unwrap(), 24 * 60),
+            (amsterdam, new_york, Utc.with_ymd_and_hms(2021, 1, 1, 12, 0, 0).unwrap(), 7),
+        ];
+
+        for (from, to, departed_at, duration_minutes) in flights {
+            assert_eq!(night_minutes(from, to, departed_at, duration_minutes), night_minutes_per_minute(from, to, departed_at, duration_minutes));
+        }
+    }
+}
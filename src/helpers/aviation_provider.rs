@@ -0,0 +1,454 @@
+use std::{env, fmt, io, path::PathBuf, sync::Arc};
+use serenity::async_trait;
+use serde::de::DeserializeOwned;
+use reqwest::{Client as ReqwestClient, StatusCode};
+use urlencoding::encode as url_encode;
+use tracing::warn;
+use crate::models::avwx::{AvwxIcao, AvwxMetar, AvwxTaf, AvwxError, AvwxNearStation};
+use crate::models::checkwx::{CheckWxResponse, CheckWxStation, CheckWxError};
+use crate::models::metar::{Metar, Taf};
+
+/// A source of station information, METARs and TAFs.
+///
+/// Stations are returned as `AvwxIcao` no matter the backend, So the embeds don't care where the data came from.
+#[async_trait]
+pub trait AviationProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn station(&self, ident: &str) -> Result<AvwxIcao, ProviderError>;
+
+    async fn metar(&self, ident: &str) -> Result<Metar, ProviderError>;
+
+    /// The METAR without going through a cache, So a SPECI issued in between is seen right away.
+    async fn fresh_metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+        self.metar(ident).await
+    }
+
+    async fn taf(&self, ident: &str) -> Result<Taf, ProviderError>;
+
+    /// Up to `count` stations around a point, Closest first.
+    async fn nearest(&self, latitude: f32, longitude: f32, count: usize) -> Result<Vec<AvwxIcao>, ProviderError>;
+}
+
+#[derive(Debug)]
+pub enum ProviderError {
+    Request(reqwest::Error),
+    Decode(String),
+    NotFound(String),
+    RateLimited(&'static str),
+    Unsupported(String),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::Request(why) => write!(f, "Request failed: {}", why),
+            ProviderError::Decode(why) => write!(f, "Couldn't decode the response: {}", why),
+            ProviderError::NotFound(why) => write!(f, "Not found: {}", why),
+            ProviderError::RateLimited(provider) => write!(f, "Rate limited by {}", provider),
+            ProviderError::Unsupported(why) => write!(f, "Not supported: {}", why),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {}
+
+/// Why `AVIATION_PROVIDER` or `NOTAM_SOURCE` couldn't be turned into a provider.
+#[derive(Debug)]
+pub enum ProviderConfigError {
+    UnknownProvider(String),
+    MissingVariable(&'static str),
+}
+
+impl fmt::Display for ProviderConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderConfigError::UnknownProvider(name) => write!(f, "Unknown provider: {}", name),
+            ProviderConfigError::MissingVariable(variable) => write!(f, "{} isn't set", variable),
+        }
+    }
+}
+
+impl std::error::Error for ProviderConfigError {}
+
+impl From<reqwest::Error> for ProviderError {
+    fn from(why: reqwest::Error) -> Self {
+        if why.is_decode() {
+            ProviderError::Decode(why.to_string())
+        } else {
+            ProviderError::Request(why)
+        }
+    }
+}
+
+/// Builds the provider chosen with `AVIATION_PROVIDER`.
+///
+/// A comma separated list like `avwx,checkwx` fails over to the next provider when one is rate limited or unreachable.
+pub fn provider_from_env(reqwest_client: ReqwestClient) -> Result<Arc<dyn AviationProvider>, ProviderConfigError> {
+    let provider_names = env::var("AVIATION_PROVIDER").unwrap_or_else(|_| String::from("avwx"));
+
+    provider_from_names(&provider_names, |variable| env::var(variable).ok(), reqwest_client)
+}
+
+/// Builds the providers in a list like `avwx,checkwx`, Their tokens and directories come from `variable`.
+pub fn provider_from_names(provider_names: &str, variable: impl Fn(&'static str) -> Option<String>,
+                           reqwest_client: ReqwestClient) -> Result<Arc<dyn AviationProvider>, ProviderConfigError> {
+    let required_variable = |name: &'static str| variable(name).ok_or(ProviderConfigError::MissingVariable(name));
+
+    let mut providers: Vec<Arc<dyn AviationProvider>> = Vec::new();
+    for provider_name in provider_names.split(',').map(|name| name.trim().to_lowercase()) {
+        match provider_name.as_str() {
+            "avwx" => providers.push(Arc::new(AvwxProvider::new(reqwest_client.clone(), required_variable("AVWX_TOKEN")?))),
+            "checkwx" => providers.push(Arc::new(CheckWxProvider::new(reqwest_client.clone(), required_variable("CHECKWX_TOKEN")?))),
+            "local" => providers.push(Arc::new(LocalProvider::new(PathBuf::from(required_variable("LOCAL_WEATHER_DIR")?)))),
+            _ => return Err(ProviderConfigError::UnknownProvider(provider_name)),
+        }
+    }
+
+    if providers.len() == 1 {
+        Ok(providers.remove(0))
+    } else {
+        Ok(Arc::new(FallbackProvider { providers }))
+    }
+}
+
+pub struct AvwxProvider {
+    reqwest_client: ReqwestClient,
+    token: String,
+}
+
+impl AvwxProvider {
+    pub fn new(reqwest_client: ReqwestClient, token: String) -> Self {
+        AvwxProvider { reqwest_client, token }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, endpoint: &str, ident: &str) -> Result<T, ProviderError> {
+        self.get_path(&format!("{}/{}?format=json", endpoint, url_encode(ident))).await
+    }
+
+    async fn get_path<T: DeserializeOwned>(&self, path: &str) -> Result<T, ProviderError> {
+        let avwx_response = self.reqwest_client.get(format!("https://avwx.rest/api/{}", path))
+            .header(reqwest::header::AUTHORIZATION, format!("Token {}", self.token))
+            .send().await?;
+
+        match avwx_response.status() {
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::RateLimited(self.name())),
+            status if status.is_client_error() => {
+                // AVWX explains what was wrong with the ident
+                match avwx_response.json::<AvwxError>().await {
+                    Ok(avwx_error) => Err(ProviderError::NotFound(avwx_error.error)),
+                    Err(_) => Err(ProviderError::NotFound(format!("{} returned {}", self.name(), status))),
+                }
+            }
+            _ => Ok(avwx_response.json::<T>().await?),
+        }
+    }
+}
+
+#[async_trait]
+impl AviationProvider for AvwxProvider {
+    fn name(&self) -> &'static str {
+        "AVWX"
+    }
+
+    async fn station(&self, ident: &str) -> Result<AvwxIcao, ProviderError> {
+        self.get::<AvwxIcao>("station", ident).await
+    }
+
+    async fn metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+        Ok(Metar::from(&self.get::<AvwxMetar>("metar", ident).await?))
+    }
+
+    async fn taf(&self, ident: &str) -> Result<Taf, ProviderError> {
+        Ok(Taf::from(&self.get::<AvwxTaf>("taf", ident).await?))
+    }
+
+    async fn nearest(&self, latitude: f32, longitude: f32, count: usize) -> Result<Vec<AvwxIcao>, ProviderError> {
+        let near_stations = self.get_path::<Vec<AvwxNearStation>>(
+            &format!("station/near/{},{}?n={}&airport=true&reporting=false&format=json", latitude, longitude, count.min(200))).await?;
+
+        Ok(near_stations.into_iter().map(|near_station| near_station.station).collect())
+    }
+}
+
+/// CheckWX only hands out raw reports on the free plan, Those are decoded with the offline parser.
+pub struct CheckWxProvider {
+    reqwest_client: ReqwestClient,
+    api_key: String,
+}
+
+impl CheckWxProvider {
+    pub fn new(reqwest_client: ReqwestClient, api_key: String) -> Self {
+        CheckWxProvider { reqwest_client, api_key }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, endpoint: &str, ident: &str) -> Result<T, ProviderError> {
+        let mut checkwx_data = self.get_all::<T>(&format!("{}/{}", endpoint, url_encode(ident))).await?;
+        if checkwx_data.is_empty() {
+            Err(ProviderError::NotFound(format!("{} has no {} for {}", self.name(), endpoint, ident)))
+        } else {
+            Ok(checkwx_data.remove(0))
+        }
+    }
+
+    async fn get_all<T: DeserializeOwned>(&self, path: &str) -> Result<Vec<T>, ProviderError> {
+        let checkwx_response = self.reqwest_client.get(format!("https://api.checkwx.com/{}", path))
+            .header("X-API-Key", &self.api_key)
+            .send().await?;
+
+        match checkwx_response.status() {
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::RateLimited(self.name())),
+            status if status.is_client_error() => {
+                match checkwx_response.json::<CheckWxError>().await {
+                    Ok(checkwx_error) => Err(ProviderError::NotFound(checkwx_error.error)),
+                    Err(_) => Err(ProviderError::NotFound(format!("{} returned {}", self.name(), status))),
+                }
+            }
+            // Every endpoint wraps the results in a list
+            _ => Ok(checkwx_response.json::<CheckWxResponse<T>>().await?.data),
+        }
+    }
+}
+
+#[async_trait]
+impl AviationProvider for CheckWxProvider {
+    fn name(&self) -> &'static str {
+        "CheckWX"
+    }
+
+    async fn station(&self, ident: &str) -> Result<AvwxIcao, ProviderError> {
+        Ok(AvwxIcao::from(self.get::<CheckWxStation>("station", ident).await?))
+    }
+
+    async fn metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+        let raw = self.get::<String>("metar", ident).await?;
+        Metar::parse(&raw).map_err(|why| ProviderError::Decode(why.to_string()))
+    }
+
+    async fn taf(&self, ident: &str) -> Result<Taf, ProviderError> {
+        let raw = self.get::<String>("taf", ident).await?;
+        Taf::parse(&raw).map_err(|why| ProviderError::Decode(why.to_string()))
+    }
+
+    async fn nearest(&self, latitude: f32, longitude: f32, count: usize) -> Result<Vec<AvwxIcao>, ProviderError> {
+        // CheckWX wants a radius in miles, 250 is the most it allows
+        let stations = self.get_all::<CheckWxStation>(&format!("station/lat/{}/lon/{}/radius/250", latitude, longitude)).await?;
+
+        Ok(stations.into_iter().take(count).map(AvwxIcao::from).collect())
+    }
+}
+
+pub const LOCAL_PROVIDER_NAME: &str = "Local";
+
+/// Reads reports from files in a directory, Like `EHAM.metar`, `EHAM.taf` and `EHAM.json` for the station in the AVWX format.
+///
+/// Meant for development, Editing a file is enough to try the weather alerts without waiting on the real weather.
+pub struct LocalProvider {
+    directory: PathBuf,
+}
+
+impl LocalProvider {
+    pub fn new(directory: PathBuf) -> Self {
+        LocalProvider { directory }
+    }
+
+    async fn read(&self, ident: &str, extension: &str) -> Result<String, ProviderError> {
+        let path = self.directory.join(format!("{}.{}", ident.to_uppercase(), extension));
+
+        match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => Ok(contents),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => Err(ProviderError::NotFound(format!("There is no {}", path.display()))),
+            Err(why) => Err(ProviderError::Decode(format!("Couldn't read {}: {}", path.display(), why))),
+        }
+    }
+}
+
+#[async_trait]
+impl AviationProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        LOCAL_PROVIDER_NAME
+    }
+
+    async fn station(&self, ident: &str) -> Result<AvwxIcao, ProviderError> {
+        let contents = self.read(ident, "json").await?;
+        serde_json::from_str(&contents).map_err(|why| ProviderError::Decode(why.to_string()))
+    }
+
+    async fn metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+        let raw = self.read(ident, "metar").await?;
+        Metar::parse(raw.trim()).map_err(|why| ProviderError::Decode(why.to_string()))
+    }
+
+    async fn taf(&self, ident: &str) -> Result<Taf, ProviderError> {
+        let raw = self.read(ident, "taf").await?;
+        Taf::parse(raw.trim()).map_err(|why| ProviderError::Decode(why.to_string()))
+    }
+
+    // There is no index of the files to search through
+    async fn nearest(&self, _latitude: f32, _longitude: f32, _count: usize) -> Result<Vec<AvwxIcao>, ProviderError> {
+        Err(ProviderError::Unsupported(format!("{} reports can't be searched for stations nearby", self.name())))
+    }
+}
+
+/// Tries every provider in order until one of them isn't rate limited, Down or unable to answer.
+pub struct FallbackProvider {
+    providers: Vec<Arc<dyn AviationProvider>>,
+}
+
+impl FallbackProvider {
+    fn should_fail_over(why: &ProviderError) -> bool {
+        matches!(why, ProviderError::RateLimited(_) | ProviderError::Request(_) | ProviderError::Unsupported(_))
+    }
+}
+
+// Runs the same call on every provider until one answers.
+macro_rules! fail_over {
+    ($self:ident, $method:ident, $($argument:expr),+) => {{
+        let mut last_error = None;
+        for provider in &$self.providers {
+            match provider.$method($($argument),+).await {
+                Err(why) if FallbackProvider::should_fail_over(&why) => {
+                    warn!("{} failed, Trying the next provider: {}", provider.name(), why);
+                    last_error = Some(why);
+                }
+                result => return result,
+            }
+        }
+        // Only without any providers, Which provider_from_env doesn't build
+        Err(last_error.unwrap_or_else(|| ProviderError::NotFound(String::from("There is no aviation provider to ask"))))
+    }};
+}
+
+#[async_trait]
+impl AviationProvider for FallbackProvider {
+    fn name(&self) -> &'static str {
+        self.providers[0].name()
+    }
+
+    async fn station(&self, ident: &str) -> Result<AvwxIcao, ProviderError> {
+        fail_over!(self, station, ident)
+    }
+
+    async fn metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+        fail_over!(self, metar, ident)
+    }
+
+    async fn taf(&self, ident: &str) -> Result<Taf, ProviderError> {
+        fail_over!(self, taf, ident)
+    }
+
+    async fn nearest(&self, latitude: f32, longitude: f32, count: usize) -> Result<Vec<AvwxIcao>, ProviderError> {
+        fail_over!(self, nearest, latitude, longitude, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Answers every call the same way and counts how often it was asked
+    struct FakeProvider {
+        name: &'static str,
+        failure: Option<fn() -> ProviderError>,
+        calls: AtomicUsize,
+    }
+
+    impl FakeProvider {
+        fn new(name: &'static str, failure: Option<fn() -> ProviderError>) -> Arc<FakeProvider> {
+            Arc::new(FakeProvider { name, failure, calls: AtomicUsize::new(0) })
+        }
+    }
+
+    #[async_trait]
+    impl AviationProvider for FakeProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn station(&self, ident: &str) -> Result<AvwxIcao, ProviderError> {
+            Err(ProviderError::NotFound(ident.to_string()))
+        }
+
+        async fn metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.failure {
+                Some(failure) => Err(failure()),
+                None => Ok(Metar::parse(&format!("{} 181225Z 22008KT CAVOK 16/07 Q1019", ident)).unwrap()),
+            }
+        }
+
+        async fn taf(&self, ident: &str) -> Result<Taf, ProviderError> {
+            Err(ProviderError::NotFound(ident.to_string()))
+        }
+
+        async fn nearest(&self, _latitude: f32, _longitude: f32, _count: usize) -> Result<Vec<AvwxIcao>, ProviderError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_when_rate_limited() {
+        let (first, second) = (FakeProvider::new("First", Some(|| ProviderError::RateLimited("First"))), FakeProvider::new("Second", None));
+        let fallback_provider = FallbackProvider { providers: vec![first.clone(), second.clone()] };
+
+        assert_eq!(fallback_provider.metar("EHAM").await.unwrap().station, "EHAM");
+        assert_eq!((first.calls.load(Ordering::SeqCst), second.calls.load(Ordering::SeqCst)), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn doesnt_fail_over_when_not_found() {
+        let (first, second) = (FakeProvider::new("First", Some(|| ProviderError::NotFound(String::from("EHAM")))), FakeProvider::new("Second", None));
+        let fallback_provider = FallbackProvider { providers: vec![first, second.clone()] };
+
+        assert!(matches!(fallback_provider.metar("EHAM").await, Err(ProviderError::NotFound(_))));
+        assert_eq!(second.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn returns_the_last_error_when_every_provider_fails() {
+        let fallback_provider = FallbackProvider { providers: vec![
+            FakeProvider::new("First", Some(|| ProviderError::RateLimited("First"))),
+            FakeProvider::new("Second", Some(|| ProviderError::RateLimited("Second"))),
+        ] };
+        assert!(matches!(fallback_provider.metar("EHAM").await, Err(ProviderError::RateLimited("Second"))));
+
+        let empty_provider = FallbackProvider { providers: Vec::new() };
+        assert!(matches!(empty_provider.metar("EHAM").await, Err(ProviderError::NotFound(_))));
+    }
+
+    fn variables(name: &'static str) -> Option<String> {
+        match name {
+            "AVWX_TOKEN" => Some(String::from("token")),
+            "LOCAL_WEATHER_DIR" => Some(String::from("weather")),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_over_when_unsupported() {
+        let fallback_provider = FallbackProvider { providers: vec![Arc::new(LocalProvider::new(PathBuf::from("weather"))), FakeProvider::new("Second", None)] };
+        assert!(fallback_provider.nearest(52.31, 4.76, 10).await.is_ok());
+
+        let local_provider = LocalProvider::new(PathBuf::from("weather"));
+        assert!(matches!(local_provider.nearest(52.31, 4.76, 10).await, Err(ProviderError::Unsupported(_))));
+    }
+
+    #[test]
+    fn builds_providers_from_names() {
+        assert_eq!(provider_from_names("local", variables, ReqwestClient::new()).unwrap().name(), LOCAL_PROVIDER_NAME);
+        assert_eq!(provider_from_names(" AVWX , local", variables, ReqwestClient::new()).unwrap().name(), "AVWX");
+    }
+
+    #[test]
+    fn rejects_unknown_providers() {
+        let provider = provider_from_names("avwx,unknown", variables, ReqwestClient::new());
+        assert!(matches!(provider, Err(ProviderConfigError::UnknownProvider(name)) if name == "unknown"));
+    }
+
+    #[test]
+    fn rejects_missing_tokens() {
+        let provider = provider_from_names("avwx,checkwx", variables, ReqwestClient::new());
+        assert!(matches!(provider, Err(ProviderConfigError::MissingVariable("CHECKWX_TOKEN"))));
+    }
+}
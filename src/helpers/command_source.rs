@@ -0,0 +1,156 @@
+// Where a command came from, So prefix and slash commands can share the same command logic.
+use std::sync::atomic::{AtomicU64, Ordering};
+use serenity::prelude::Context;
+use serenity::builder::{CreateEmbed, CreateComponents};
+use serenity::model::prelude::*;
+use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+
+enum SourceKind<'a> {
+    Message(&'a Message),
+    Interaction(&'a ApplicationCommandInteraction),
+}
+
+/// A prefix command message or a slash command interaction.
+///
+/// Slash commands are expected to be deferred before the command runs, The first reply then answers the interaction and the rest are follow-ups.
+pub struct CommandSource<'a> {
+    kind: SourceKind<'a>,
+    // The id of the message that answered the interaction, Zero until there is one
+    original_response: AtomicU64,
+}
+
+impl<'a> From<&'a Message> for CommandSource<'a> {
+    fn from(msg: &'a Message) -> Self {
+        CommandSource { kind: SourceKind::Message(msg), original_response: AtomicU64::new(0) }
+    }
+}
+
+impl<'a> From<&'a ApplicationCommandInteraction> for CommandSource<'a> {
+    fn from(interaction: &'a ApplicationCommandInteraction) -> Self {
+        CommandSource { kind: SourceKind::Interaction(interaction), original_response: AtomicU64::new(0) }
+    }
+}
+
+impl<'a> CommandSource<'a> {
+    pub fn author(&self) -> &User {
+        match self.kind {
+            SourceKind::Message(msg) => &msg.author,
+            SourceKind::Interaction(interaction) => &interaction.user,
+        }
+    }
+
+    pub fn channel_id(&self) -> ChannelId {
+        match self.kind {
+            SourceKind::Message(msg) => msg.channel_id,
+            SourceKind::Interaction(interaction) => interaction.channel_id,
+        }
+    }
+
+    pub fn guild_id(&self) -> Option<GuildId> {
+        match self.kind {
+            SourceKind::Message(msg) => msg.guild_id,
+            SourceKind::Interaction(interaction) => interaction.guild_id,
+        }
+    }
+
+    /// The id of the message or interaction, Messages sent before the command have a lower one.
+    pub fn id(&self) -> u64 {
+        match self.kind {
+            SourceKind::Message(msg) => msg.id.0,
+            SourceKind::Interaction(interaction) => interaction.id.0,
+        }
+    }
+
+    pub async fn say(&self, ctx: &Context, content: impl ToString) -> serenity::Result<Message> {
+        self.send(ctx, content.to_string(), None, None).await
+    }
+
+    pub async fn send_embed(&self, ctx: &Context, embed: CreateEmbed) -> serenity::Result<Message> {
+        self.send(ctx, String::new(), Some(embed), None).await
+    }
+
+    pub async fn send_embed_with_components(&self, ctx: &Context, embed: CreateEmbed, components: CreateComponents) -> serenity::Result<Message> {
+        self.send(ctx, String::new(), Some(embed), Some(components)).await
+    }
+
+    async fn send(&self, ctx: &Context, content: String, embed: Option<CreateEmbed>, components: Option<CreateComponents>) -> serenity::Result<Message> {
+        match self.kind {
+            SourceKind::Message(msg) => msg.channel_id.send_message(&ctx.http, |m| {
+                m.content(content);
+                if let Some(embed) = embed { m.embed(|e| { e.0 = embed.0; e }); }
+                if let Some(components) = components { m.set_components(components); }
+                m
+            }).await,
+            SourceKind::Interaction(interaction) if self.original_response.load(Ordering::SeqCst) == 0 => {
+                let sent_message = interaction.edit_original_interaction_response(&ctx.http, |r| {
+                    r.content(content);
+                    if let Some(embed) = embed { r.add_embed(embed); }
+                    if let Some(components) = components { r.components(|c| { c.0 = components.0; c }); }
+                    r
+                }).await?;
+                self.original_response.store(sent_message.id.0, Ordering::SeqCst);
+
+                Ok(sent_message)
+            }
+            SourceKind::Interaction(interaction) => interaction.create_followup_message(&ctx.http, |f| {
+                f.content(content);
+                if let Some(embed) = embed { f.add_embed(embed); }
+                if let Some(components) = components { f.set_components(components); }
+                f
+            }).await,
+        }
+    }
+
+    /// Edits a message sent with this source, The embed is kept when None is given.
+    pub async fn edit(&self, ctx: &Context, message: &mut Message, content: impl ToString, embed: Option<CreateEmbed>) -> serenity::Result<()> {
+        match self.kind {
+            SourceKind::Message(_) => message.edit(&ctx, |m| {
+                m.content(content);
+                if let Some(embed) = embed { m.embed(|e| { e.0 = embed.0; e }); }
+                m
+            }).await,
+            SourceKind::Interaction(interaction) if message.id.0 == self.original_response.load(Ordering::SeqCst) => {
+                *message = interaction.edit_original_interaction_response(&ctx.http, |r| {
+                    r.content(content);
+                    if let Some(embed) = embed { r.set_embeds(vec![embed]); }
+                    r
+                }).await?;
+                Ok(())
+            }
+            SourceKind::Interaction(interaction) => {
+                *message = interaction.edit_followup_message(&ctx.http, message.id, |f| {
+                    f.content(content);
+                    if let Some(embed) = embed { f.embeds(vec![embed]); }
+                    f
+                }).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Replaces the components of a message sent with this source, An empty set removes them.
+    pub async fn edit_components(&self, ctx: &Context, message: &mut Message, components: CreateComponents) -> serenity::Result<()> {
+        match self.kind {
+            SourceKind::Message(_) => message.edit(&ctx, |m| m.components(|c| { c.0 = components.0; c })).await,
+            SourceKind::Interaction(interaction) if message.id.0 == self.original_response.load(Ordering::SeqCst) => {
+                *message = interaction.edit_original_interaction_response(&ctx.http, |r| r.components(|c| { c.0 = components.0; c })).await?;
+                Ok(())
+            }
+            SourceKind::Interaction(interaction) => {
+                *message = interaction.edit_followup_message(&ctx.http, message.id, |f| f.set_components(components)).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Deletes a message sent with this source.
+    pub async fn delete(&self, ctx: &Context, message: &Message) -> serenity::Result<()> {
+        match self.kind {
+            SourceKind::Message(_) => message.delete(&ctx).await,
+            SourceKind::Interaction(interaction) if message.id.0 == self.original_response.load(Ordering::SeqCst) => {
+                interaction.delete_original_interaction_response(&ctx.http).await
+            }
+            SourceKind::Interaction(interaction) => interaction.delete_followup_message(&ctx.http, message.id).await,
+        }
+    }
+}
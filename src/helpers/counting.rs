@@ -0,0 +1,254 @@
+// The counting game, Every message in a counting channel has to be the next number.
+use std::{fmt, time::Duration};
+use serde::{Serialize, Deserialize};
+use serenity::prelude::Context;
+use serenity::model::prelude::*;
+use tracing::warn;
+use crate::helpers::database_helper::{DatabaseGuild, DatabaseCountingStats, GuildCounting};
+use crate::helpers::global_data::CountingCache;
+use crate::helpers::counting_modes::CountingMode;
+
+// How long the notice about a deleted count stays up
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+const COUNT_ATTEMPTS: usize = 3;
+
+/// The rules of a counting channel.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct CountingRules {
+    #[serde(default)]
+    pub allow_consecutive: bool,
+    #[serde(default)]
+    pub on_fail: CountingFailAction,
+}
+
+/// What happens to the count when someone gets it wrong.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum CountingFailAction {
+    // The wrong message is deleted and the count stays
+    #[default]
+    Delete,
+    // The count starts over from zero
+    Reset,
+}
+
+impl CountingFailAction {
+    pub fn parse(name: &str) -> Option<CountingFailAction> {
+        match name.to_lowercase().as_str() {
+            "delete" => Some(CountingFailAction::Delete),
+            "reset" => Some(CountingFailAction::Reset),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CountingRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} counting twice in a row, {} on a wrong count",
+               if self.allow_consecutive { "Allows" } else { "Disallows" },
+               match self.on_fail {
+                   CountingFailAction::Delete => "deletes the message",
+                   CountingFailAction::Reset => "resets the count",
+               })
+    }
+}
+
+impl GuildCounting {
+    pub fn new(channel: ChannelId, rules: CountingRules, mode: CountingMode) -> Self {
+        GuildCounting { channel: channel.0 as i64, count: 0, last_user: None, high_score: 0, last_message: None, rules, mode }
+    }
+}
+
+/// Checks a message sent in a counting channel, Messages in other channels are ignored.
+pub async fn handle_counting_message(ctx: &Context, msg: &Message) {
+    let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+    // The announcements of the bot itself aren't counts
+    if !counting_cache.contains_key(&msg.channel_id) || msg.is_own(&ctx).await {
+        return;
+    }
+
+    let (guild, user) = (msg.guild_id.unwrap().0 as i64, msg.author.id.0 as i64);
+    // Another count can get in while this one is saved, It's then checked again against that one
+    for _ in 0..COUNT_ATTEMPTS {
+        // The counting is copied like this so the reference is dropped.
+        let counting = match counting_cache.get(&msg.channel_id) {
+            Some(counting_map) => *counting_map.value(),
+            None => return,
+        };
+
+        // Parse the message content in the counting mode, Otherwise delete the message.
+        let new_number = match counting.mode.parse_count(&msg.content) {
+            Some(new_number) => new_number,
+            None => {
+                let _ = msg.delete(&ctx).await;
+                return;
+            }
+        };
+
+        let failure = if new_number != counting.count + 1 {
+            Some(format!("the next number was {}", counting.mode.format_count(counting.count + 1)))
+        } else if !counting.rules.allow_consecutive && counting.last_user == Some(user) {
+            Some(String::from("you can't count twice in a row"))
+        } else {
+            None
+        };
+
+        match failure {
+            None => {
+                let mut new_counting = counting;
+                new_counting.count = new_number;
+                new_counting.last_user = Some(user);
+                new_counting.high_score = new_number.max(counting.high_score);
+                new_counting.last_message = Some(msg.id.0 as i64);
+                if !save_count(ctx, guild, counting, new_counting).await {
+                    continue;
+                }
+                DatabaseCountingStats::record_count(ctx, guild, counting.channel, user).await;
+
+                // Only the count that beats the record is announced, Not every one after it
+                if counting.high_score > 0 && new_number == counting.high_score + 1 {
+                    let _ = msg.channel_id.say(ctx, format!(":trophy: New record! <@{}> beat the old record of **{}**.", user, counting.high_score)).await;
+                }
+            }
+            Some(reason) => match counting.rules.on_fail {
+                CountingFailAction::Delete => {
+                    DatabaseCountingStats::record_mistake(ctx, guild, counting.channel, user).await;
+                    let _ = msg.delete(&ctx).await;
+                    if let Ok(notice) = msg.channel_id.say(ctx, format!(":no_entry_sign: <@{}>, {}.", user, reason)).await {
+                        // Delete the notice later, So the next count doesn't wait on it
+                        let ctx = ctx.clone();
+                        tokio::spawn(async move {
+                            tokio::time::sleep(NOTICE_DURATION).await;
+                            let _ = notice.delete(&ctx).await;
+                        });
+                    }
+                }
+                CountingFailAction::Reset => {
+                    let mut new_counting = counting;
+                    new_counting.count = 0;
+                    new_counting.last_user = None;
+                    new_counting.last_message = None;
+                    if !save_count(ctx, guild, counting, new_counting).await {
+                        continue;
+                    }
+                    DatabaseCountingStats::record_mistake(ctx, guild, counting.channel, user).await;
+                    DatabaseCountingStats::end_streak(ctx, guild, counting.channel, counting.count).await;
+
+                    let _ = msg.react(&ctx, ReactionType::Unicode(String::from("❌"))).await;
+                    let _ = msg.channel_id.say(ctx, format!(":x: <@{}> broke the streak at **{}**, {}. The record is **{}**, Start again from {}.",
+                                                            user, counting.count, reason, counting.high_score, counting.mode.format_count(1))).await;
+                }
+            },
+        }
+
+        return;
+    }
+
+    warn!("Gave up checking a count in {} after {} attempts", msg.channel_id, COUNT_ATTEMPTS);
+}
+
+/// Reposts the count when the message it's at is edited to something else, Other edits are ignored.
+pub async fn handle_counting_edit(ctx: &Context, event: &MessageUpdateEvent) {
+    let counting = match current_count_message(ctx, event.channel_id, event.id).await {
+        Some(counting) => counting,
+        None => return,
+    };
+
+    // Embeds loading in also come as edits, Those don't change the content
+    let edited_number = match &event.content {
+        Some(content) => counting.mode.parse_count(content),
+        None => return,
+    };
+    if edited_number != Some(counting.count) {
+        repost_count(ctx, event.guild_id, counting, "edited").await;
+    }
+}
+
+/// Reposts the count when the message it's at is deleted.
+pub async fn handle_counting_delete(ctx: &Context, channel_id: ChannelId, message_id: MessageId, guild_id: Option<GuildId>) {
+    if let Some(counting) = current_count_message(ctx, channel_id, message_id).await {
+        repost_count(ctx, guild_id, counting, "deleted").await;
+    }
+}
+
+/// Reposts the count when a purge deletes the message it's at.
+pub async fn handle_counting_delete_bulk(ctx: &Context, channel_id: ChannelId, message_ids: &[MessageId], guild_id: Option<GuildId>) {
+    // Only one of them can be the message the count is at
+    for message_id in message_ids {
+        if let Some(counting) = current_count_message(ctx, channel_id, *message_id).await {
+            repost_count(ctx, guild_id, counting, "deleted").await;
+            return;
+        }
+    }
+}
+
+// The counting of the channel when the message is the one it's at
+async fn current_count_message(ctx: &Context, channel_id: ChannelId, message_id: MessageId) -> Option<GuildCounting> {
+    let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+    let counting = *counting_cache.get(&channel_id)?.value();
+
+    if counting.last_message == Some(message_id.0 as i64) { Some(counting) } else { None }
+}
+
+// The repost isn't tracked, So it being deleted too doesn't repost it again
+async fn repost_count(ctx: &Context, guild_id: Option<GuildId>, counting: GuildCounting, action: &str) {
+    let channel = ChannelId::from(counting.channel as u64);
+    // Events don't always have the guild, The cached channel does
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => match ctx.cache.guild_channel_field(channel, |channel| channel.guild_id).await {
+            Some(guild_id) => guild_id,
+            None => return,
+        },
+    };
+
+    let mut new_counting = counting;
+    new_counting.last_message = None;
+    if !save_count(ctx, guild_id.0 as i64, counting, new_counting).await {
+        return;
+    }
+
+    let user = counting.last_user.map(|user| format!("<@{}>", user)).unwrap_or_else(|| String::from("Someone"));
+    let _ = channel.say(ctx, format!(":warning: {} {} their count, It was **{}**. The next number is {}.",
+                                     user, action, counting.mode.format_count(counting.count), counting.mode.format_count(counting.count + 1))).await;
+}
+
+// Saves the new count when the channel is still at the count it was checked against and puts it in the cache.
+// Otherwise the cache gets the stored counting, So the message can be checked again.
+async fn save_count(ctx: &Context, guild: i64, counting: GuildCounting, new_counting: GuildCounting) -> bool {
+    let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
+    let channel = ChannelId::from(counting.channel as u64);
+
+    if DatabaseGuild::update_count(ctx, guild, counting.count, &new_counting).await {
+        // The rules and mode can change meanwhile, And a newer count can already be cached
+        if let Some(mut cached_counting) = counting_cache.get_mut(&channel) {
+            if cached_counting.count == counting.count && cached_counting.last_message == counting.last_message {
+                cached_counting.count = new_counting.count;
+                cached_counting.last_user = new_counting.last_user;
+                cached_counting.high_score = new_counting.high_score;
+                cached_counting.last_message = new_counting.last_message;
+            }
+        }
+        return true;
+    }
+
+    // Another count got in first, Or the channel was removed
+    match DatabaseGuild::get_counting(ctx, guild, counting.channel).await {
+        Some(stored_counting) => { counting_cache.insert(channel, stored_counting); }
+        None => { counting_cache.remove(&channel); }
+    }
+
+    false
+}
+
+/// The counting channel a command is about when none is given, This channel when it counts and otherwise the only one of the guild.
+pub fn pick_counting_channel(counting: &[GuildCounting], current_channel: ChannelId) -> Result<ChannelId, &'static str> {
+    if counting.iter().any(|counting| counting.channel == current_channel.0 as i64) {
+        return Ok(current_channel);
+    }
+
+    match counting {
+        [] => Err("There is no counting channel in this server."),
+        [counting] => Ok(ChannelId::from(counting.channel as u64)),
+        _ => Err("This server has several counting channels, Say which one."),
+    }
+}
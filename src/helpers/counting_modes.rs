@@ -0,0 +1,274 @@
+// The ways a count can be written in a counting channel.
+use std::{convert::TryFrom, fmt};
+use serde::{Serialize, Deserialize};
+
+// Keeps the evaluator's recursion and work small, Longer messages aren't counts anyway
+const MAX_EXPRESSION_LENGTH: usize = 64;
+const ROMAN_NUMERALS: [(i64, &str); 13] = [(1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+    (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")];
+// The largest number roman numerals have a usual way of writing, Counts past it are decimal
+const MAX_ROMAN: i64 = 3999;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub enum CountingMode {
+    #[default]
+    Decimal,
+    // Arithmetic like 5*4+1, Plain numbers count too
+    Math,
+    Hexadecimal,
+    Binary,
+    Roman,
+}
+
+impl CountingMode {
+    pub fn parse(name: &str) -> Option<CountingMode> {
+        match name.to_lowercase().as_str() {
+            "decimal" | "normal" | "numbers" => Some(CountingMode::Decimal),
+            "math" | "maths" | "expressions" => Some(CountingMode::Math),
+            "hex" | "hexadecimal" => Some(CountingMode::Hexadecimal),
+            "binary" | "bin" => Some(CountingMode::Binary),
+            "roman" => Some(CountingMode::Roman),
+            _ => None,
+        }
+    }
+
+    /// The number a message counts as, None when it isn't one in this mode.
+    pub fn parse_count(&self, content: &str) -> Option<i64> {
+        let content = content.trim();
+        match self {
+            CountingMode::Decimal => content.parse::<i64>().ok(),
+            CountingMode::Math => evaluate_expression(content),
+            CountingMode::Hexadecimal => parse_radix(content, &["0x", "0X"], 16),
+            CountingMode::Binary => parse_radix(content, &["0b", "0B"], 2),
+            CountingMode::Roman => parse_roman(content).or_else(|| content.parse::<i64>().ok().filter(|number| *number > MAX_ROMAN)),
+        }
+    }
+
+    /// Writes the number like it should be counted in this mode.
+    pub fn format_count(&self, number: i64) -> String {
+        match self {
+            CountingMode::Decimal | CountingMode::Math => number.to_string(),
+            CountingMode::Hexadecimal => format!("{:X}", number),
+            CountingMode::Binary => format!("{:b}", number),
+            CountingMode::Roman => format_roman(number).unwrap_or_else(|| number.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for CountingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CountingMode::Decimal => write!(f, "decimal"),
+            CountingMode::Math => write!(f, "math"),
+            CountingMode::Hexadecimal => write!(f, "hexadecimal"),
+            CountingMode::Binary => write!(f, "binary"),
+            CountingMode::Roman => write!(f, "roman numerals"),
+        }
+    }
+}
+
+// Digits in the radix with an optional prefix, Signs aren't allowed
+fn parse_radix(content: &str, prefixes: &[&str], radix: u32) -> Option<i64> {
+    let digits = prefixes.iter().find_map(|prefix| content.strip_prefix(prefix)).unwrap_or(content);
+    if digits.is_empty() || !digits.chars().all(|digit| digit.is_digit(radix)) {
+        return None;
+    }
+
+    i64::from_str_radix(digits, radix).ok()
+}
+
+// Only the usual way of writing a number is accepted, So IIII or IC aren't
+fn parse_roman(content: &str) -> Option<i64> {
+    let numeral = content.to_uppercase();
+    let mut rest = numeral.as_str();
+    let mut number: i64 = 0;
+    for (value, symbol) in ROMAN_NUMERALS.iter() {
+        while let Some(remaining) = rest.strip_prefix(symbol) {
+            number = number.checked_add(*value).filter(|number| *number <= MAX_ROMAN)?;
+            rest = remaining;
+        }
+    }
+
+    if rest.is_empty() && format_roman(number).as_deref() == Some(numeral.as_str()) {
+        Some(number)
+    } else {
+        None
+    }
+}
+
+// None outside of 1 to 3999
+fn format_roman(mut number: i64) -> Option<String> {
+    if !(1..=MAX_ROMAN).contains(&number) {
+        return None;
+    }
+
+    let mut numeral = String::new();
+    for (value, symbol) in ROMAN_NUMERALS.iter() {
+        while number >= *value {
+            numeral += symbol;
+            number -= value;
+        }
+    }
+
+    Some(numeral)
+}
+
+/// Evaluates integer arithmetic with + - * / % ^ and parentheses.
+///
+/// Nothing is allocated and every operation is checked, Overflows, division by zero and
+/// divisions with a remainder make it None.
+pub fn evaluate_expression(expression: &str) -> Option<i64> {
+    if expression.is_empty() || expression.len() > MAX_EXPRESSION_LENGTH {
+        return None;
+    }
+
+    let mut parser = ExpressionParser { bytes: expression.as_bytes(), pos: 0 };
+    let value = parser.sum()?;
+    parser.skip_whitespace();
+
+    if parser.pos == parser.bytes.len() { Some(value) } else { None }
+}
+
+struct ExpressionParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    // The next operator when it's one of these, Which is then skipped
+    fn operator(&mut self, operators: &[u8]) -> Option<u8> {
+        self.skip_whitespace();
+        let operator = *self.bytes.get(self.pos).filter(|byte| operators.contains(byte))?;
+        self.pos += 1;
+
+        Some(operator)
+    }
+
+    fn sum(&mut self) -> Option<i64> {
+        let mut value = self.product()?;
+        while let Some(operator) = self.operator(b"+-") {
+            let right = self.product()?;
+            value = if operator == b'+' { value.checked_add(right)? } else { value.checked_sub(right)? };
+        }
+
+        Some(value)
+    }
+
+    fn product(&mut self) -> Option<i64> {
+        let mut value = self.power()?;
+        while let Some(operator) = self.operator(b"*/%") {
+            let right = self.power()?;
+            value = match operator {
+                b'*' => value.checked_mul(right)?,
+                b'/' if value.checked_rem(right)? == 0 => value.checked_div(right)?,
+                b'%' => value.checked_rem(right)?,
+                _ => return None,
+            };
+        }
+
+        Some(value)
+    }
+
+    // Right associative, So 2^3^2 is 2^9
+    fn power(&mut self) -> Option<i64> {
+        let base = self.unary()?;
+        if self.operator(b"^").is_some() {
+            let exponent = u32::try_from(self.power()?).ok()?;
+            return base.checked_pow(exponent);
+        }
+
+        Some(base)
+    }
+
+    fn unary(&mut self) -> Option<i64> {
+        if self.operator(b"-").is_some() {
+            return self.unary()?.checked_neg();
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Option<i64> {
+        if self.operator(b"(").is_some() {
+            let value = self.sum()?;
+            self.operator(b")")?;
+            return Some(value);
+        }
+
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut value: i64 = 0;
+        while let Some(digit) = self.bytes.get(self.pos).filter(|byte| byte.is_ascii_digit()) {
+            value = value.checked_mul(10)?.checked_add((digit - b'0') as i64)?;
+            self.pos += 1;
+        }
+
+        if self.pos > start { Some(value) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_with_precedence() {
+        assert_eq!(evaluate_expression("2+3*4"), Some(14));
+        assert_eq!(evaluate_expression("(2+3)*4"), Some(20));
+        assert_eq!(evaluate_expression("2^3^2"), Some(512));
+        assert_eq!(evaluate_expression("-2^2"), Some(4));
+        assert_eq!(evaluate_expression("10-4-3"), Some(3));
+        assert_eq!(evaluate_expression(" 7 % 4 + 8 / 2 "), Some(7));
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_whole_number() {
+        assert_eq!(evaluate_expression("2^63"), None);
+        assert_eq!(evaluate_expression("2^62*2"), None);
+        assert_eq!(evaluate_expression("9223372036854775808"), None);
+        assert_eq!(evaluate_expression("2^-1"), None);
+        assert_eq!(evaluate_expression("7/2"), None);
+        assert_eq!(evaluate_expression("7/0"), None);
+        assert_eq!(evaluate_expression("7%0"), None);
+        assert_eq!(evaluate_expression("(1+2"), None);
+        assert_eq!(evaluate_expression("1 2"), None);
+        assert_eq!(evaluate_expression(""), None);
+        assert_eq!(evaluate_expression(&"1+".repeat(40)), None);
+    }
+
+    #[test]
+    fn counts_round_trip() {
+        let modes = [CountingMode::Decimal, CountingMode::Math, CountingMode::Hexadecimal, CountingMode::Binary, CountingMode::Roman];
+        for mode in modes.iter() {
+            for number in (1..=4100).chain([i64::MAX]) {
+                assert_eq!(mode.parse_count(&mode.format_count(number)), Some(number), "{} in {}", number, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn parses_counts_per_mode() {
+        assert_eq!(CountingMode::Hexadecimal.parse_count("0xff"), Some(255));
+        assert_eq!(CountingMode::Hexadecimal.parse_count("-ff"), None);
+        assert_eq!(CountingMode::Binary.parse_count("0b101"), Some(5));
+        assert_eq!(CountingMode::Binary.parse_count("102"), None);
+        assert_eq!(CountingMode::Roman.parse_count("mcmxcix"), Some(1999));
+        assert_eq!(CountingMode::Roman.parse_count("IIII"), None);
+        assert_eq!(CountingMode::Roman.parse_count("IC"), None);
+        assert_eq!(CountingMode::Roman.parse_count("MMMM"), None);
+        assert_eq!(CountingMode::Roman.parse_count("12"), None);
+    }
+
+    #[test]
+    fn writes_roman_numerals_up_to_3999() {
+        assert_eq!(CountingMode::Roman.format_count(3999), "MMMCMXCIX");
+        assert_eq!(CountingMode::Roman.format_count(4000), "4000");
+        assert_eq!(CountingMode::Roman.format_count(i64::MAX), i64::MAX.to_string());
+    }
+}
@@ -3,20 +3,63 @@ use crate::helpers::global_data::Database;
 use std::env;
 use mongodb::Collection;
 use mongodb::bson::{doc, Document};
-use serde::{Serialize, Deserialize};
-use mongodb::options::FindOneAndReplaceOptions;
+use serde::{Serialize, Deserialize, Deserializer};
+use mongodb::options::{FindOneAndReplaceOptions, FindOptions, UpdateOptions};
+use mongodb::bson::oid::ObjectId;
+use serenity::futures::StreamExt;
+use crate::models::units::UserUnits;
+use crate::helpers::weather_alerts::AlertConditions;
+use crate::helpers::counting::CountingRules;
+use crate::helpers::counting_modes::CountingMode;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DatabaseGuild {
     pub _id: i64,
     pub prefix: Option<String>,
-    pub counting: Option<GuildCounting>,
+    #[serde(default, deserialize_with = "deserialize_counting")]
+    pub counting: Vec<GuildCounting>,
+    #[serde(default)]
+    pub weather_alerts: Vec<WeatherAlert>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
 pub struct GuildCounting {
     pub channel: i64,
     pub count: i64,
+    #[serde(default)]
+    pub last_user: Option<i64>,
+    #[serde(default)]
+    pub high_score: i64,
+    // The message with the count it's at, So editing or deleting it can be noticed
+    #[serde(default)]
+    pub last_message: Option<i64>,
+    #[serde(default)]
+    pub rules: CountingRules,
+    #[serde(default)]
+    pub mode: CountingMode,
+}
+
+// Guilds used to have one counting channel, Those documents have a single one or null instead of a list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredCounting {
+    Many(Vec<GuildCounting>),
+    One(GuildCounting),
+}
+
+fn deserialize_counting<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<GuildCounting>, D::Error> {
+    Ok(match Option::<StoredCounting>::deserialize(deserializer)? {
+        Some(StoredCounting::Many(counting)) => counting,
+        Some(StoredCounting::One(counting)) => vec![counting],
+        None => Vec::new(),
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WeatherAlert {
+    pub channel: i64,
+    pub ident: String,
+    pub conditions: AlertConditions,
 }
 
 impl DatabaseGuild {
@@ -28,7 +71,8 @@ impl DatabaseGuild {
                 DatabaseGuild::insert_or_replace(ctx, DatabaseGuild {
                     _id,
                     prefix: None,
-                    counting: None
+                    counting: Vec::new(),
+                    weather_alerts: Vec::new(),
                 }).await
             ).unwrap()
         } else {
@@ -59,6 +103,60 @@ impl DatabaseGuild {
         }
     }
 
+    // These only set their own field, So a count that comes in meanwhile isn't overwritten
+    pub(crate) async fn set_prefix(ctx: &Context, _id: i64, prefix: Option<String>) {
+        let mut update_options = UpdateOptions::default();
+        update_options.upsert = Some(true);
+
+        let update = doc! { "$set": { "prefix": bson::to_bson(&prefix).unwrap() } };
+        DatabaseGuild::get_collection(ctx).await.update_one(doc! { "_id": _id }, update, update_options).await.unwrap();
+    }
+
+    pub(crate) async fn set_weather_alerts(ctx: &Context, _id: i64, weather_alerts: &[WeatherAlert]) {
+        let mut update_options = UpdateOptions::default();
+        update_options.upsert = Some(true);
+
+        let update = doc! { "$set": { "weather_alerts": bson::to_bson(weather_alerts).unwrap() } };
+        DatabaseGuild::get_collection(ctx).await.update_one(doc! { "_id": _id }, update, update_options).await.unwrap();
+    }
+
+    pub(crate) async fn get_counting(ctx: &Context, _id: i64, channel: i64) -> Option<GuildCounting> {
+        let database_guild = DatabaseGuild::get_or_insert_new(ctx, _id).await;
+
+        database_guild.counting.into_iter().find(|counting| counting.channel == channel)
+    }
+
+    /// Moves a counting channel on from the count it's expected to be at, False when it isn't at that count anymore.
+    pub(crate) async fn update_count(ctx: &Context, _id: i64, expected_count: i64, counting: &GuildCounting) -> bool {
+        let filter = doc! { "_id": _id, "counting": { "$elemMatch": { "channel": counting.channel, "count": expected_count } } };
+        let update = doc! {
+            "$inc": { "counting.$.count": counting.count - expected_count },
+            "$set": { "counting.$.last_user": bson::to_bson(&counting.last_user).unwrap(),
+                       "counting.$.last_message": bson::to_bson(&counting.last_message).unwrap() },
+            "$max": { "counting.$.high_score": counting.high_score },
+        };
+        let update_result = DatabaseGuild::get_collection(ctx).await.update_one(filter, update, None).await.unwrap();
+
+        update_result.matched_count == 1
+    }
+
+    // These only touch their counting channel, So counts that come in meanwhile aren't overwritten
+    pub(crate) async fn add_counting(ctx: &Context, _id: i64, counting: &GuildCounting) {
+        let update = doc! { "$push": { "counting": bson::to_bson(counting).unwrap() } };
+        DatabaseGuild::get_collection(ctx).await.update_one(doc! { "_id": _id }, update, None).await.unwrap();
+    }
+
+    pub(crate) async fn remove_counting(ctx: &Context, _id: i64, channel: i64) {
+        let update = doc! { "$pull": { "counting": { "channel": channel } } };
+        DatabaseGuild::get_collection(ctx).await.update_one(doc! { "_id": _id }, update, None).await.unwrap();
+    }
+
+    pub(crate) async fn update_counting_settings(ctx: &Context, _id: i64, channel: i64, rules: CountingRules, mode: CountingMode) {
+        let filter = doc! { "_id": _id, "counting.channel": channel };
+        let update = doc! { "$set": { "counting.$.rules": bson::to_bson(&rules).unwrap(), "counting.$.mode": bson::to_bson(&mode).unwrap() } };
+        DatabaseGuild::get_collection(ctx).await.update_one(filter, update, None).await.unwrap();
+    }
+
     pub(crate) async fn delete(ctx: &Context, id: i64) -> mongodb::error::Result<Option<Document>> {
         let document_id = doc! { "_id": id };
         DatabaseGuild::get_collection(ctx).await.find_one_and_delete(document_id, None).await
@@ -70,4 +168,199 @@ impl DatabaseGuild {
 
         database.collection("guilds")
     }
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DatabaseUser {
+    pub _id: i64,
+    #[serde(default)]
+    pub units: UserUnits,
+    pub home_airport: Option<String>,
+}
+
+impl DatabaseUser {
+    // Users without a document just get the defaults, There's no need to store those.
+    pub(crate) async fn get_or_default(ctx: &Context, _id: i64) -> DatabaseUser {
+        let document_id = doc! { "_id": _id };
+        let document = DatabaseUser::get_collection(ctx).await.find_one(document_id, None).await.unwrap();
+
+        match document {
+            Some(document) => bson::from_document(document).unwrap(),
+            None => DatabaseUser {
+                _id,
+                units: UserUnits::default(),
+                home_airport: None,
+            },
+        }
+    }
+
+    pub(crate) async fn insert_or_replace(ctx: &Context, database_user: DatabaseUser) -> Document {
+        let new_document = bson::to_document(&database_user).unwrap();
+
+        let mut replace_options = FindOneAndReplaceOptions::default();
+        replace_options.upsert = Some(true);
+
+        let collection = DatabaseUser::get_collection(ctx).await;
+        // Find and replace the document and return it
+        match collection.find_one_and_replace(doc! { "_id": database_user._id }, new_document, replace_options).await.unwrap() {
+            Some(document) => document,
+            None => {
+                collection.find_one(doc! { "_id": database_user._id }, None).await.unwrap().unwrap()
+            }
+        }
+    }
+
+    pub(crate) async fn get_collection(ctx: &Context) -> Collection {
+        let mongo_database = env::var("MONGO_DATABASE").unwrap();
+        let database = ctx.data.read().await.get::<Database>().unwrap().database(&mongo_database);
+
+        database.collection("users")
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DatabaseFlight {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub user: i64,
+    pub from: String,
+    pub to: String,
+    pub aircraft: String,
+    pub landed_at: bson::DateTime,
+    pub duration_minutes: i64,
+    pub night_minutes: i64,
+    pub notes: Option<String>,
+}
+
+impl DatabaseFlight {
+    pub(crate) async fn insert(ctx: &Context, database_flight: &DatabaseFlight) {
+        let new_document = bson::to_document(database_flight).unwrap();
+
+        DatabaseFlight::get_collection(ctx).await.insert_one(new_document, None).await.unwrap();
+    }
+
+    // Every flight of a user, The latest first.
+    pub(crate) async fn get_for_user(ctx: &Context, user: i64) -> Vec<DatabaseFlight> {
+        let find_options = FindOptions::builder().sort(doc! { "landed_at": -1 }).build();
+        let mut cursor = DatabaseFlight::get_collection(ctx).await.find(doc! { "user": user }, find_options).await.unwrap();
+
+        let mut database_flights = Vec::new();
+        while let Some(document) = cursor.next().await {
+            database_flights.push(bson::from_document(document.unwrap()).unwrap());
+        }
+
+        database_flights
+    }
+
+    // A page of a user's flights, The latest first.
+    pub(crate) async fn get_page_for_user(ctx: &Context, user: i64, skip: i64, limit: i64) -> Vec<DatabaseFlight> {
+        let find_options = FindOptions::builder().sort(doc! { "landed_at": -1 }).skip(skip).limit(limit).build();
+        let mut cursor = DatabaseFlight::get_collection(ctx).await.find(doc! { "user": user }, find_options).await.unwrap();
+
+        let mut database_flights = Vec::new();
+        while let Some(document) = cursor.next().await {
+            database_flights.push(bson::from_document(document.unwrap()).unwrap());
+        }
+
+        database_flights
+    }
+
+    pub(crate) async fn count_for_user(ctx: &Context, user: i64) -> i64 {
+        DatabaseFlight::get_collection(ctx).await.count_documents(doc! { "user": user }, None).await.unwrap()
+    }
+
+    pub(crate) async fn get_collection(ctx: &Context) -> Collection {
+        let mongo_database = env::var("MONGO_DATABASE").unwrap();
+        let database = ctx.data.read().await.get::<Database>().unwrap().database(&mongo_database);
+
+        database.collection("flights")
+    }
+}
+
+/// How a user did in a counting channel.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DatabaseCountingStats {
+    pub guild: i64,
+    pub channel: i64,
+    pub user: i64,
+    #[serde(default)]
+    pub counts: i64,
+    #[serde(default)]
+    pub mistakes: i64,
+    // The longest streak the user counted in that has ended
+    #[serde(default)]
+    pub best_streak: i64,
+    // Whether the user counted in the streak that's still going
+    #[serde(default)]
+    pub in_streak: bool,
+}
+
+impl DatabaseCountingStats {
+    pub(crate) async fn record_count(ctx: &Context, guild: i64, channel: i64, user: i64) {
+        let update = doc! { "$inc": { "counts": 1 }, "$set": { "in_streak": true } };
+        DatabaseCountingStats::upsert(ctx, doc! { "guild": guild, "channel": channel, "user": user }, update).await;
+    }
+
+    pub(crate) async fn record_mistake(ctx: &Context, guild: i64, channel: i64, user: i64) {
+        DatabaseCountingStats::upsert(ctx, doc! { "guild": guild, "channel": channel, "user": user }, doc! { "$inc": { "mistakes": 1 } }).await;
+    }
+
+    // Everyone who counted in the streak gets it as their best when it's longer
+    pub(crate) async fn end_streak(ctx: &Context, guild: i64, channel: i64, streak: i64) {
+        let filter = doc! { "guild": guild, "channel": channel, "in_streak": true };
+        let update = doc! { "$max": { "best_streak": streak }, "$set": { "in_streak": false } };
+        DatabaseCountingStats::get_collection(ctx).await.update_many(filter, update, None).await.unwrap();
+    }
+
+    async fn upsert(ctx: &Context, filter: Document, update: Document) {
+        let update_options = UpdateOptions::builder().upsert(true).build();
+        DatabaseCountingStats::get_collection(ctx).await.update_one(filter, update, update_options).await.unwrap();
+    }
+
+    // A page of the leaderboard of a counting channel, The most counts first.
+    pub(crate) async fn get_page_for_channel(ctx: &Context, guild: i64, channel: i64, skip: i64, limit: i64) -> Vec<DatabaseCountingStats> {
+        let find_options = FindOptions::builder().sort(doc! { "counts": -1, "mistakes": 1 }).skip(skip).limit(limit).build();
+        let mut cursor = DatabaseCountingStats::get_collection(ctx).await.find(doc! { "guild": guild, "channel": channel }, find_options).await.unwrap();
+
+        let mut counting_stats = Vec::new();
+        while let Some(document) = cursor.next().await {
+            counting_stats.push(bson::from_document(document.unwrap()).unwrap());
+        }
+
+        counting_stats
+    }
+
+    pub(crate) async fn count_for_channel(ctx: &Context, guild: i64, channel: i64) -> i64 {
+        DatabaseCountingStats::get_collection(ctx).await.count_documents(doc! { "guild": guild, "channel": channel }, None).await.unwrap()
+    }
+
+    // The stats of a user in every counting channel of a guild
+    pub(crate) async fn get_for_user(ctx: &Context, guild: i64, user: i64) -> Vec<DatabaseCountingStats> {
+        let find_options = FindOptions::builder().sort(doc! { "counts": -1 }).build();
+        let mut cursor = DatabaseCountingStats::get_collection(ctx).await.find(doc! { "guild": guild, "user": user }, find_options).await.unwrap();
+
+        let mut counting_stats = Vec::new();
+        while let Some(document) = cursor.next().await {
+            counting_stats.push(bson::from_document(document.unwrap()).unwrap());
+        }
+
+        counting_stats
+    }
+
+    // The place of a user on the leaderboard, Starting at 1
+    pub(crate) async fn rank(ctx: &Context, counting_stats: &DatabaseCountingStats) -> i64 {
+        let filter = doc! { "guild": counting_stats.guild, "channel": counting_stats.channel, "counts": { "$gt": counting_stats.counts } };
+        DatabaseCountingStats::get_collection(ctx).await.count_documents(filter, None).await.unwrap() + 1
+    }
+
+    pub(crate) async fn delete_for_guild(ctx: &Context, guild: i64) {
+        DatabaseCountingStats::get_collection(ctx).await.delete_many(doc! { "guild": guild }, None).await.unwrap();
+    }
+
+    pub(crate) async fn get_collection(ctx: &Context) -> Collection {
+        let mongo_database = env::var("MONGO_DATABASE").unwrap();
+        let database = ctx.data.read().await.get::<Database>().unwrap().database(&mongo_database);
+
+        database.collection("counting_stats")
+    }
+}
@@ -5,12 +5,23 @@ use serenity::model::id::{ChannelId, GuildId};
 use std::sync::Arc;
 use dashmap::DashMap;
 use reqwest::Client as ReqwestClient;
+use crate::helpers::aviation_provider::AviationProvider;
+use crate::helpers::response_cache::ResponseCache;
+use crate::helpers::database_helper::{WeatherAlert, GuildCounting};
+use crate::helpers::notam_source::NotamSource;
+use crate::helpers::airport_database::AirportDatabase;
+use serenity::prelude::RwLock;
 
 pub struct Database;
 pub struct Uptime;
 pub struct CountingCache;
 pub struct PrefixCache;
 pub struct ReqwestContainer;
+pub struct AviationProviderContainer;
+pub struct AviationCache;
+pub struct WeatherAlertCache;
+pub struct NotamSourceContainer;
+pub struct AirportDatabaseContainer;
 
 impl TypeMapKey for Database {
     type Value = MongoClient;
@@ -21,7 +32,7 @@ impl TypeMapKey for Uptime {
 }
 
 impl TypeMapKey for CountingCache {
-    type Value = Arc<DashMap<ChannelId, i64>>;
+    type Value = Arc<DashMap<ChannelId, GuildCounting>>;
 }
 
 impl TypeMapKey for PrefixCache {
@@ -30,4 +41,24 @@ impl TypeMapKey for PrefixCache {
 
 impl TypeMapKey for ReqwestContainer {
     type Value = ReqwestClient;
+}
+
+impl TypeMapKey for AviationProviderContainer {
+    type Value = Arc<dyn AviationProvider>;
+}
+
+impl TypeMapKey for AviationCache {
+    type Value = Arc<ResponseCache>;
+}
+
+impl TypeMapKey for WeatherAlertCache {
+    type Value = Arc<DashMap<GuildId, Vec<WeatherAlert>>>;
+}
+
+impl TypeMapKey for NotamSourceContainer {
+    type Value = Option<Arc<dyn NotamSource>>;
+}
+
+impl TypeMapKey for AirportDatabaseContainer {
+    type Value = Arc<RwLock<AirportDatabase>>;
 }
\ No newline at end of file
@@ -1,3 +1,13 @@
 pub mod global_data;
 pub mod database_helper;
-pub mod general_helper;
\ No newline at end of file
+pub mod general_helper;
+pub mod aviation_provider;
+pub mod response_cache;
+pub mod aviation_helper;
+pub mod weather_alerts;
+pub mod notam_source;
+pub mod airport_database;
+pub mod command_source;
+pub mod paginator;
+pub mod counting;
+pub mod counting_modes;
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::{env, io, path::PathBuf, sync::Arc};
+use serenity::async_trait;
+use reqwest::{Client as ReqwestClient, StatusCode};
+use crate::helpers::aviation_provider::{ProviderError, ProviderConfigError};
+use crate::models::notam::{Notam, FaaNotamResponse};
+
+const DEFAULT_FAA_NOTAM_URL: &str = "https://external-api.faa.gov/notamapi/v1/notams";
+
+/// A source of active NOTAMs for a station.
+#[async_trait]
+pub trait NotamSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn notams(&self, ident: &str) -> Result<Vec<Notam>, ProviderError>;
+}
+
+/// Builds the source chosen with `NOTAM_SOURCE`, None when NOTAMs aren't set up for this deployment.
+pub fn notam_source_from_env(reqwest_client: ReqwestClient) -> Result<Option<Arc<dyn NotamSource>>, ProviderConfigError> {
+    let source_name = match env::var("NOTAM_SOURCE") {
+        Ok(source_name) => source_name.trim().to_lowercase(),
+        Err(_) => return Ok(None),
+    };
+    let required_variable = |name: &'static str| env::var(name).map_err(|_| ProviderConfigError::MissingVariable(name));
+
+    match source_name.as_str() {
+        "faa" => Ok(Some(Arc::new(FaaNotamSource {
+            reqwest_client,
+            url: env::var("NOTAM_API_URL").unwrap_or_else(|_| String::from(DEFAULT_FAA_NOTAM_URL)),
+            client_id: required_variable("NOTAM_CLIENT_ID")?,
+            client_secret: required_variable("NOTAM_CLIENT_SECRET")?,
+        }))),
+        "local" => Ok(Some(Arc::new(LocalNotamSource { directory: PathBuf::from(required_variable("LOCAL_WEATHER_DIR")?) }))),
+        "" | "none" => Ok(None),
+        _ => Err(ProviderConfigError::UnknownProvider(source_name)),
+    }
+}
+
+/// The FAA NOTAM API, Which also has the ICAO NOTAMs of most airports outside the US.
+pub struct FaaNotamSource {
+    reqwest_client: ReqwestClient,
+    url: String,
+    client_id: String,
+    client_secret: String,
+}
+
+#[async_trait]
+impl NotamSource for FaaNotamSource {
+    fn name(&self) -> &'static str {
+        "FAA"
+    }
+
+    async fn notams(&self, ident: &str) -> Result<Vec<Notam>, ProviderError> {
+        let faa_response = self.reqwest_client.get(&self.url)
+            .query(&[("icaoLocation", ident), ("pageSize", "1000")])
+            .header("client_id", &self.client_id)
+            .header("client_secret", &self.client_secret)
+            .send().await?;
+
+        match faa_response.status() {
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::RateLimited(self.name())),
+            status if status.is_client_error() => Err(ProviderError::NotFound(format!("{} returned {}", self.name(), status))),
+            _ => Ok(faa_response.json::<FaaNotamResponse>().await?.items.into_iter()
+                .map(|item| Notam::from(item.properties.core_notam_data.notam))
+                .collect()),
+        }
+    }
+}
+
+/// Reads `EHAM.notam` from a directory, With a blank line between the NOTAMs.
+///
+/// The first line of every NOTAM is used as its id.
+pub struct LocalNotamSource {
+    directory: PathBuf,
+}
+
+#[async_trait]
+impl NotamSource for LocalNotamSource {
+    fn name(&self) -> &'static str {
+        "Local"
+    }
+
+    async fn notams(&self, ident: &str) -> Result<Vec<Notam>, ProviderError> {
+        let path = self.directory.join(format!("{}.notam", ident.to_uppercase()));
+        let contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            // No file means no NOTAMs
+            Err(why) if why.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(why) => return Err(ProviderError::Decode(format!("Couldn't read {}: {}", path.display(), why))),
+        };
+
+        Ok(contents.replace("\r\n", "\n").split("\n\n")
+            .filter_map(|block| {
+                let (id, text) = block.trim().split_once('\n')?;
+                Some(Notam::new(id.trim().to_string(), text.trim().to_string(), None, None))
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,261 @@
+// Menus to page through embeds, With message buttons or with reactions where the buttons can't be sent.
+use std::future::Future;
+use std::time::Duration;
+use serenity::prelude::*;
+use serenity::model::prelude::*;
+use serenity::model::interactions::message_component::ButtonStyle;
+use serenity::builder::{CreateEmbed, CreateComponents};
+use serenity::framework::standard::CommandResult;
+use serenity::futures::{StreamExt, FutureExt, future::BoxFuture};
+use tracing::warn;
+use crate::helpers::command_source::CommandSource;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+type PageLoader<'a> = Box<dyn Fn(usize) -> BoxFuture<'a, CreateEmbed> + Send + Sync + 'a>;
+
+enum PageSource<'a> {
+    Static(Vec<CreateEmbed>),
+    // Pages are loaded the first time they're shown and kept after that
+    Lazy { loader: PageLoader<'a>, loaded: Vec<Option<CreateEmbed>> },
+}
+
+// Who can navigate the menu
+enum MenuOwner {
+    Author,
+    Everyone,
+}
+
+/// A menu to page through embeds.
+///
+/// It's sent with first/previous/next/last/close buttons, Or with ⬅ and ➡ reactions when the buttons can't be sent.
+/// Every page gets a footer with its page number.
+pub struct Paginator<'a> {
+    pages: PageSource<'a>,
+    page_count: usize,
+    owner: MenuOwner,
+    timeout: Duration,
+}
+
+impl<'a> Paginator<'a> {
+    pub fn new(pages: Vec<CreateEmbed>) -> Self {
+        Paginator::with_source(pages.len(), PageSource::Static(pages))
+    }
+
+    /// A menu with `page_count` pages, Which the loader builds when they're first shown.
+    pub fn lazy<F, Fut>(page_count: usize, loader: F) -> Self
+        where F: Fn(usize) -> Fut + Send + Sync + 'a, Fut: Future<Output = CreateEmbed> + Send + 'a {
+        let loader: PageLoader<'a> = Box::new(move |index| loader(index).boxed());
+        Paginator::with_source(page_count, PageSource::Lazy { loader, loaded: vec![None; page_count] })
+    }
+
+    fn with_source(page_count: usize, pages: PageSource<'a>) -> Self {
+        Paginator { pages, page_count, owner: MenuOwner::Author, timeout: DEFAULT_TIMEOUT }
+    }
+
+    /// Lets everyone navigate the menu, Instead of only the author of the command.
+    pub fn for_everyone(mut self) -> Self {
+        self.owner = MenuOwner::Everyone;
+        self
+    }
+
+    /// How long the menu can be navigated for, Five minutes when it isn't set.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub async fn send(mut self, ctx: &Context, source: &CommandSource<'_>) -> CommandResult {
+        if self.page_count == 0 {
+            return Ok(());
+        }
+        let owner = match self.owner {
+            MenuOwner::Author => Some(source.author().id),
+            MenuOwner::Everyone => None,
+        };
+
+        // There is nothing to navigate with only one page
+        let first_page = self.page(0).await;
+        if self.page_count < 2 {
+            source.send_embed(ctx, first_page).await?;
+            return Ok(());
+        }
+
+        match source.send_embed_with_components(ctx, first_page.clone(), menu_buttons(0, self.page_count)).await {
+            Ok(sent_message) => self.run_buttons(ctx, source, sent_message, owner).await,
+            Err(why) => {
+                warn!("Couldn't send the menu buttons, Using reactions instead: {:?}", why);
+                let sent_message = source.send_embed(ctx, first_page).await?;
+                self.run_reactions(ctx, source, sent_message, owner).await
+            }
+        }
+    }
+
+    fn is_loaded(&self, index: usize) -> bool {
+        match &self.pages {
+            PageSource::Static(_) => true,
+            PageSource::Lazy { loaded, .. } => loaded[index].is_some(),
+        }
+    }
+
+    // The page with its page number in the footer
+    async fn page(&mut self, index: usize) -> CreateEmbed {
+        let mut page = match &mut self.pages {
+            PageSource::Static(pages) => pages[index].clone(),
+            PageSource::Lazy { loader, loaded } => match &loaded[index] {
+                Some(page) => page.clone(),
+                None => {
+                    let page = loader(index).await;
+                    loaded[index] = Some(page.clone());
+                    page
+                }
+            },
+        };
+
+        if self.page_count > 1 {
+            add_page_indicator(&mut page, index, self.page_count);
+        }
+
+        page
+    }
+
+    async fn run_buttons(&mut self, ctx: &Context, source: &CommandSource<'_>, mut sent_message: Message, owner: Option<UserId>) -> CommandResult {
+        // Position in the menu
+        let mut current_pos: usize = 0;
+        let last_pos = self.page_count - 1;
+
+        let mut interactions = sent_message.await_component_interactions(&ctx).timeout(self.timeout).await;
+        while let Some(interaction) = interactions.next().await {
+            // Only the owner can navigate, Everyone else gets told so
+            if owner.is_some_and(|owner| owner != interaction.user.id) {
+                interaction.create_interaction_response(&ctx.http, |r| r
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|d| d.content(":no_entry_sign: Only the user who ran the command can use this menu.")
+                        .flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL))
+                ).await?;
+                continue;
+            }
+
+            current_pos = match interaction.data.custom_id.as_str() {
+                "first" => 0,
+                "previous" => current_pos.saturating_sub(1),
+                "next" => (current_pos + 1).min(last_pos),
+                "last" => last_pos,
+                "close" => {
+                    // Keep the current page, But remove the buttons
+                    interaction.create_interaction_response(&ctx.http, |r| r
+                        .kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| d.components(|c| c))
+                    ).await?;
+                    return Ok(());
+                }
+                _ => continue,
+            };
+
+            // Answering with the new page edits the message, Pages that still have to load can take longer than Discord waits for an answer
+            if self.is_loaded(current_pos) {
+                let page = self.page(current_pos).await;
+                interaction.create_interaction_response(&ctx.http, |r| r
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| d.embeds(vec![page]).set_components(menu_buttons(current_pos, self.page_count)))
+                ).await?;
+            } else {
+                interaction.defer(&ctx.http).await?;
+                let page = self.page(current_pos).await;
+                interaction.edit_original_interaction_response(&ctx.http, |r| r
+                    .set_embeds(vec![page])
+                    .components(|c| { c.0 = menu_buttons(current_pos, self.page_count).0; c })
+                ).await?;
+            }
+        }
+
+        // Remove the buttons once the menu times out
+        source.edit_components(ctx, &mut sent_message, CreateComponents::default()).await?;
+
+        Ok(())
+    }
+
+    async fn run_reactions(&mut self, ctx: &Context, source: &CommandSource<'_>, mut sent_message: Message, owner: Option<UserId>) -> CommandResult {
+        // Position in the menu
+        let mut current_pos: usize = 0;
+
+        // React the menu
+        sent_message.react(&ctx.http, ReactionType::Unicode(String::from("⬅"))).await?;
+        sent_message.react(&ctx.http, ReactionType::Unicode(String::from("➡"))).await?;
+
+        // Check if the bot can manage messages, And if so enable the bot to remove reactions.
+        let mut can_manage_messages = false;
+        let guild = match source.guild_id() {
+            Some(guild_id) => guild_id.to_guild_cached(&ctx).await,
+            None => None,
+        };
+        if let Some(guild) = guild {
+            can_manage_messages = guild
+                .user_permissions_in(guild.channels.get(&source.channel_id()).unwrap(), guild.members.get(&ctx.http.get_current_user().await?.id).unwrap())
+                .unwrap().contains(Permissions::MANAGE_MESSAGES);
+        }
+
+        // Create a reaction collector and wait for someone to react to the message
+        let mut reactions_builder = sent_message.await_reactions(&ctx).timeout(self.timeout).added(true).removed(!can_manage_messages);
+        if let Some(owner) = owner {
+            reactions_builder = reactions_builder.author_id(owner);
+        }
+        let mut reactions_collector = reactions_builder.await;
+        while let Some(reaction) = reactions_collector.next().await {
+            // Delete the reaction
+            if can_manage_messages {
+                reaction.as_inner_ref().delete(&ctx.http).await?;
+            }
+
+            let emoji = &reaction.as_inner_ref().emoji.to_string();
+            let new_pos = if emoji == "⬅" {
+                current_pos.saturating_sub(1)
+            } else if emoji == "➡" {
+                (current_pos + 1).min(self.page_count - 1)
+            } else {
+                current_pos
+            };
+
+            // Edit the original message
+            if new_pos != current_pos {
+                current_pos = new_pos;
+                let page = self.page(current_pos).await;
+                source.edit(ctx, &mut sent_message, "", Some(page)).await?;
+            }
+        }
+
+        // Delete all reactions once done
+        if can_manage_messages {
+            sent_message.delete_reactions(&ctx.http).await?;
+        }
+
+        Ok(())
+    }
+}
+
+// Adds the page number after the footer the page already has
+fn add_page_indicator(page: &mut CreateEmbed, index: usize, page_count: usize) {
+    let indicator = format!("Page {}/{}", index + 1, page_count);
+    let footer_text = match page.0.get("footer").and_then(|footer| footer.get("text")).and_then(|text| text.as_str()) {
+        Some(text) if !text.is_empty() => format!("{} - {}", text, indicator),
+        _ => indicator,
+    };
+
+    page.footer(|f| f.text(footer_text));
+}
+
+fn menu_buttons(current_pos: usize, page_count: usize) -> CreateComponents {
+    let at_start = current_pos == 0;
+    let at_end = current_pos + 1 >= page_count;
+
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| row
+        .create_button(|b| b.custom_id("first").emoji(ReactionType::Unicode(String::from("⏮"))).style(ButtonStyle::Secondary).disabled(at_start))
+        .create_button(|b| b.custom_id("previous").emoji(ReactionType::Unicode(String::from("◀"))).style(ButtonStyle::Secondary).disabled(at_start))
+        .create_button(|b| b.custom_id("next").emoji(ReactionType::Unicode(String::from("▶"))).style(ButtonStyle::Secondary).disabled(at_end))
+        .create_button(|b| b.custom_id("last").emoji(ReactionType::Unicode(String::from("⏭"))).style(ButtonStyle::Secondary).disabled(at_end))
+        .create_button(|b| b.custom_id("close").emoji(ReactionType::Unicode(String::from("✖"))).style(ButtonStyle::Danger))
+    );
+
+    components
+}
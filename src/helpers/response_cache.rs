@@ -0,0 +1,207 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use chrono::Utc;
+use serenity::async_trait;
+use crate::helpers::aviation_provider::{AviationProvider, ProviderError};
+use crate::models::avwx::AvwxIcao;
+use crate::models::metar::{Metar, Taf};
+
+// Station information hardly ever changes
+const STATION_TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+const TAF_TTL: Duration = Duration::from_secs(60 * 60);
+// Used when a METAR is already late for its next issue
+const LATE_METAR_TTL: Duration = Duration::from_secs(2 * 60);
+const MAX_METAR_TTL: Duration = Duration::from_secs(60 * 60);
+// How often inserting also drops the expired entries
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum CacheEndpoint {
+    Station,
+    Metar,
+    Taf,
+}
+
+#[derive(Debug, Clone)]
+pub enum CachedResponse {
+    Station(AvwxIcao),
+    Metar(Metar),
+    Taf(Taf),
+}
+
+/// TTL cache for provider responses, Keyed by endpoint and station.
+#[derive(Default)]
+pub struct ResponseCache {
+    entries: DashMap<(CacheEndpoint, String), (Instant, CachedResponse)>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    last_sweep: Mutex<Option<Instant>>,
+}
+
+impl ResponseCache {
+    pub fn get(&self, endpoint: CacheEndpoint, ident: &str) -> Option<CachedResponse> {
+        let key = (endpoint, ident.to_uppercase());
+
+        let cached = self.entries.get(&key).map(|entry| entry.value().clone());
+        match cached {
+            Some((expires_at, response)) if expires_at > Instant::now() => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(response)
+            }
+            Some(_) => {
+                self.entries.remove(&key);
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, endpoint: CacheEndpoint, ident: &str, response: CachedResponse) {
+        let ttl = match &response {
+            CachedResponse::Station(_) => STATION_TTL,
+            CachedResponse::Metar(metar) => metar_ttl(metar),
+            CachedResponse::Taf(_) => TAF_TTL,
+        };
+
+        self.sweep_expired();
+        self.entries.insert((endpoint, ident.to_uppercase()), (Instant::now() + ttl, response));
+    }
+
+    // Entries are otherwise only dropped when they're asked for again, Which never happens for most stations
+    fn sweep_expired(&self) {
+        let now = Instant::now();
+        {
+            let mut last_sweep = self.last_sweep.lock().unwrap();
+            if last_sweep.is_some_and(|last_sweep| now.duration_since(last_sweep) < SWEEP_INTERVAL) {
+                return;
+            }
+            *last_sweep = Some(now);
+        }
+
+        self.entries.retain(|_, (expires_at, _)| *expires_at > now);
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+// METARs are issued every hour, So keep it until an hour after it was observed.
+fn metar_ttl(metar: &Metar) -> Duration {
+    let now = Utc::now();
+    let observed_at = match metar.time.and_then(|time| time.to_datetime(now)) {
+        Some(observed_at) => observed_at,
+        None => return LATE_METAR_TTL,
+    };
+
+    let next_issue = observed_at + chrono::Duration::hours(1);
+    match (next_issue - now).to_std() {
+        Ok(ttl) if ttl > LATE_METAR_TTL => ttl.min(MAX_METAR_TTL),
+        _ => LATE_METAR_TTL,
+    }
+}
+
+/// Wraps a provider so every answer goes through the `ResponseCache` first.
+pub struct CachingProvider {
+    provider: Arc<dyn AviationProvider>,
+    cache: Arc<ResponseCache>,
+}
+
+impl CachingProvider {
+    pub fn new(provider: Arc<dyn AviationProvider>, cache: Arc<ResponseCache>) -> Self {
+        CachingProvider { provider, cache }
+    }
+}
+
+#[async_trait]
+impl AviationProvider for CachingProvider {
+    fn name(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    async fn station(&self, ident: &str) -> Result<AvwxIcao, ProviderError> {
+        if let Some(CachedResponse::Station(station)) = self.cache.get(CacheEndpoint::Station, ident) {
+            return Ok(station);
+        }
+
+        let station = self.provider.station(ident).await?;
+        self.cache.insert(CacheEndpoint::Station, ident, CachedResponse::Station(station.clone()));
+        Ok(station)
+    }
+
+    async fn metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+        if let Some(CachedResponse::Metar(metar)) = self.cache.get(CacheEndpoint::Metar, ident) {
+            return Ok(metar);
+        }
+
+        self.fresh_metar(ident).await
+    }
+
+    // Still cached for the commands, Which then also see the newer report
+    async fn fresh_metar(&self, ident: &str) -> Result<Metar, ProviderError> {
+        let metar = self.provider.metar(ident).await?;
+        self.cache.insert(CacheEndpoint::Metar, ident, CachedResponse::Metar(metar.clone()));
+        Ok(metar)
+    }
+
+    async fn taf(&self, ident: &str) -> Result<Taf, ProviderError> {
+        if let Some(CachedResponse::Taf(taf)) = self.cache.get(CacheEndpoint::Taf, ident) {
+            return Ok(taf);
+        }
+
+        let taf = self.provider.taf(ident).await?;
+        self.cache.insert(CacheEndpoint::Taf, ident, CachedResponse::Taf(taf.clone()));
+        Ok(taf)
+    }
+
+    // Searches are hardly ever repeated, So they aren't cached
+    async fn nearest(&self, latitude: f32, longitude: f32, count: usize) -> Result<Vec<AvwxIcao>, ProviderError> {
+        self.provider.nearest(latitude, longitude, count).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metar(ident: &str) -> CachedResponse {
+        CachedResponse::Metar(Metar::parse(&format!("{} 181225Z 22008KT CAVOK 16/07 Q1019", ident)).unwrap())
+    }
+
+    #[test]
+    fn expired_entries_are_swept_on_insert() {
+        let cache = ResponseCache::default();
+        cache.entries.insert((CacheEndpoint::Metar, String::from("EHAM")), (Instant::now(), metar("EHAM")));
+
+        cache.insert(CacheEndpoint::Metar, "EGLL", metar("EGLL"));
+        assert_eq!(cache.entry_count(), 1);
+        assert!(cache.get(CacheEndpoint::Metar, "egll").is_some());
+    }
+
+    #[test]
+    fn sweeps_at_most_once_per_interval() {
+        let cache = ResponseCache::default();
+        cache.insert(CacheEndpoint::Metar, "EGLL", metar("EGLL"));
+        cache.entries.insert((CacheEndpoint::Metar, String::from("EHAM")), (Instant::now(), metar("EHAM")));
+
+        cache.insert(CacheEndpoint::Metar, "KJFK", metar("KJFK"));
+        assert_eq!(cache.entry_count(), 3);
+        // Reading an expired entry still drops it
+        assert!(cache.get(CacheEndpoint::Metar, "EHAM").is_none());
+        assert_eq!(cache.entry_count(), 2);
+    }
+}
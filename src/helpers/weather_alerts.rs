@@ -0,0 +1,253 @@
+// Watches the METARs of subscribed stations and posts in the channel when the weather crosses a condition.
+use std::{collections::HashMap, env, fmt, time::Duration};
+use serde::{Serialize, Deserialize};
+use serenity::prelude::Context;
+use serenity::model::id::ChannelId;
+use tracing::warn;
+use crate::helpers::database_helper::WeatherAlert;
+use crate::helpers::global_data::{AviationProviderContainer, WeatherAlertCache};
+use crate::helpers::aviation_provider::AviationProvider;
+use crate::models::metar::{Metar, FlightRules};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// What a weather alert is subscribed to, Speeds are in knots.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct AlertConditions {
+    #[serde(default)]
+    pub flight_rules: bool,
+    pub wind_kt: Option<i32>,
+    pub gust_kt: Option<i32>,
+}
+
+impl AlertConditions {
+    /// Parses arguments like `rules wind:25 gust:35`.
+    pub fn parse(arguments: &str) -> Result<AlertConditions, String> {
+        let mut conditions = AlertConditions::default();
+
+        for argument in arguments.split_whitespace().map(|argument| argument.to_lowercase()) {
+            let (key, value) = argument.split_once(':').unwrap_or((argument.as_str(), ""));
+            let threshold = || value.trim_end_matches("kt").parse::<i32>().ok().filter(|threshold| *threshold > 0)
+                .ok_or_else(|| format!("`{}` isn't a valid speed in knots.", value));
+            match key {
+                "rules" | "category" | "flight_rules" => conditions.flight_rules = true,
+                "wind" => conditions.wind_kt = Some(threshold()?),
+                "gust" | "gusts" => conditions.gust_kt = Some(threshold()?),
+                _ => return Err(format!("`{}` isn't a condition, Use rules, wind:<kt> or gust:<kt>.", argument)),
+            }
+        }
+
+        if conditions == AlertConditions::default() {
+            return Err(String::from("Give at least one condition, Like rules, wind:<kt> or gust:<kt>."));
+        }
+
+        Ok(conditions)
+    }
+}
+
+impl fmt::Display for AlertConditions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.flight_rules {
+            parts.push(String::from("flight rules changes"));
+        }
+        if let Some(wind_kt) = self.wind_kt {
+            parts.push(format!("wind above {} kt", wind_kt));
+        }
+        if let Some(gust_kt) = self.gust_kt {
+            parts.push(format!("gusts above {} kt", gust_kt));
+        }
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// The parts of a METAR the alerts look at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertSnapshot {
+    pub flight_rules: FlightRules,
+    pub wind_kt: f32,
+    /// Zero when there are no gusts.
+    pub gust_kt: f32,
+}
+
+impl From<&Metar> for AlertSnapshot {
+    fn from(metar: &Metar) -> Self {
+        AlertSnapshot {
+            flight_rules: metar.flight_rules,
+            wind_kt: metar.conditions.wind.map(|wind| wind.speed_in_knots()).unwrap_or(0.0),
+            gust_kt: metar.conditions.wind.and_then(|wind| wind.gust_in_knots()).unwrap_or(0.0),
+        }
+    }
+}
+
+/// Compares two observations of a station, Returns a message for every condition that changed.
+pub fn evaluate(ident: &str, conditions: &AlertConditions, previous: &AlertSnapshot, current: &AlertSnapshot) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    if conditions.flight_rules && previous.flight_rules != current.flight_rules {
+        messages.push(format!("{} went {}, It was {}.", ident, current.flight_rules, previous.flight_rules));
+    }
+    if let Some(message) = threshold_message(ident, "wind", conditions.wind_kt, previous.wind_kt, current.wind_kt) {
+        messages.push(message);
+    }
+    if let Some(message) = threshold_message(ident, "gusts", conditions.gust_kt, previous.gust_kt, current.gust_kt) {
+        messages.push(message);
+    }
+
+    messages
+}
+
+// Only crossing the threshold counts, Staying above it doesn't post again
+fn threshold_message(ident: &str, name: &str, threshold: Option<i32>, previous: f32, current: f32) -> Option<String> {
+    let threshold = threshold? as f32;
+
+    match (previous > threshold, current > threshold) {
+        (false, true) => Some(format!("{} {} now {:.0} kt, Above {:.0} kt.", ident, name, current, threshold)),
+        (true, false) => Some(format!("{} {} now {:.0} kt, Below {:.0} kt again.", ident, name, current, threshold)),
+        _ => None,
+    }
+}
+
+/// Polls every subscribed station forever, Meant to be spawned once the bot is ready.
+///
+/// The interval can be changed with `WEATHER_ALERT_INTERVAL` in seconds, Which helps when testing with the local provider.
+pub async fn run_weather_alerts(ctx: Context) {
+    let poll_interval = env::var("WEATHER_ALERT_INTERVAL").ok()
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_POLL_INTERVAL);
+
+    // The last observation per channel and station, Nothing is posted for the first one
+    let mut snapshots: HashMap<(ChannelId, String), AlertSnapshot> = HashMap::new();
+    let mut interval = tokio::time::interval(poll_interval);
+    loop {
+        interval.tick().await;
+
+        // Copy the alerts out so the cache isn't locked while waiting on the provider
+        let weather_alert_cache = ctx.data.read().await.get::<WeatherAlertCache>().cloned().unwrap();
+        let alerts = weather_alert_cache.iter().flat_map(|entry| entry.value().clone()).collect::<Vec<WeatherAlert>>();
+        let provider = ctx.data.read().await.get::<AviationProviderContainer>().cloned().unwrap();
+
+        for (channel, alert_text) in check_weather_alerts(provider.as_ref(), &alerts, &mut snapshots).await {
+            if let Err(why) = channel.say(&ctx, alert_text).await {
+                warn!("Couldn't post the weather alert in {}: {}", channel, why);
+            }
+        }
+    }
+}
+
+/// Checks every alert against the current METAR once, Returns the text to post per channel.
+///
+/// The snapshots keep the last observation between checks.
+pub async fn check_weather_alerts(provider: &dyn AviationProvider, alerts: &[WeatherAlert],
+                                  snapshots: &mut HashMap<(ChannelId, String), AlertSnapshot>) -> Vec<(ChannelId, String)> {
+    // Several channels can watch the same station, So fetch every METAR once
+    let mut observations: HashMap<String, Option<AlertSnapshot>> = HashMap::new();
+    let mut alert_texts = Vec::new();
+    for alert in alerts {
+        let observation = match observations.get(&alert.ident) {
+            Some(observation) => *observation,
+            None => {
+                let observation = match provider.fresh_metar(&alert.ident).await {
+                    Ok(metar) => Some(AlertSnapshot::from(&metar)),
+                    Err(why) => {
+                        warn!("Couldn't get the METAR of {} for weather alerts: {}", alert.ident, why);
+                        None
+                    }
+                };
+                observations.insert(alert.ident.clone(), observation);
+                observation
+            }
+        };
+        let current = match observation {
+            Some(current) => current,
+            None => continue,
+        };
+
+        let channel = ChannelId::from(alert.channel as u64);
+        if let Some(previous) = snapshots.insert((channel, alert.ident.clone()), current) {
+            let messages = evaluate(&alert.ident, &alert.conditions, &previous, &current);
+            if !messages.is_empty() {
+                alert_texts.push((channel, messages.iter().map(|message| format!(":warning: {}", message)).collect::<Vec<String>>().join("\n")));
+            }
+        }
+    }
+
+    // Forget the stations of removed alerts
+    snapshots.retain(|(channel, ident), _| alerts.iter().any(|alert| alert.channel == channel.0 as i64 && &alert.ident == ident));
+
+    alert_texts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use crate::helpers::aviation_provider::LocalProvider;
+    use crate::helpers::response_cache::{ResponseCache, CachingProvider};
+
+    // A directory of its own per test, So they can run at the same time
+    fn weather_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("kbot_weather_alerts_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn alert(channel: i64, ident: &str, conditions: &str) -> WeatherAlert {
+        WeatherAlert { channel, ident: ident.to_string(), conditions: AlertConditions::parse(conditions).unwrap() }
+    }
+
+    #[tokio::test]
+    async fn posts_when_the_local_weather_changes() {
+        let directory = weather_directory("changes");
+        let provider = LocalProvider::new(directory.clone());
+        let alerts = vec![alert(1, "EHAM", "rules"), alert(2, "EHAM", "wind:25 gust:35")];
+        let mut snapshots = HashMap::new();
+
+        // Nothing to compare the first observation with
+        std::fs::write(directory.join("EHAM.metar"), "EHAM 181225Z 22008KT 9999 FEW030 16/07 Q1019").unwrap();
+        assert!(check_weather_alerts(&provider, &alerts, &mut snapshots).await.is_empty());
+
+        std::fs::write(directory.join("EHAM.metar"), "EHAM 181255Z 23028G40KT 2500 RA BKN008 14/12 Q1012").unwrap();
+        let alert_texts = check_weather_alerts(&provider, &alerts, &mut snapshots).await;
+        assert_eq!(alert_texts, vec![
+            (ChannelId(1), String::from(":warning: EHAM went IFR, It was VFR.")),
+            (ChannelId(2), String::from(":warning: EHAM wind now 28 kt, Above 25 kt.\n:warning: EHAM gusts now 40 kt, Above 35 kt.")),
+        ]);
+
+        // The same weather again doesn't post again
+        assert!(check_weather_alerts(&provider, &alerts, &mut snapshots).await.is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sees_new_reports_while_the_old_one_is_cached() {
+        let directory = weather_directory("cached");
+        let provider = CachingProvider::new(Arc::new(LocalProvider::new(directory.clone())), Arc::new(ResponseCache::default()));
+        let alerts = vec![alert(1, "EHAM", "rules")];
+        let mut snapshots = HashMap::new();
+
+        std::fs::write(directory.join("EHAM.metar"), "EHAM 181225Z 22008KT 9999 FEW030 16/07 Q1019").unwrap();
+        assert!(check_weather_alerts(&provider, &alerts, &mut snapshots).await.is_empty());
+
+        // A SPECI before the cached METAR expires
+        std::fs::write(directory.join("EHAM.metar"), "EHAM 181240Z 23012KT 2500 RA BKN008 14/12 Q1012").unwrap();
+        let alert_texts = check_weather_alerts(&provider, &alerts, &mut snapshots).await;
+        assert_eq!(alert_texts, vec![(ChannelId(1), String::from(":warning: EHAM went IFR, It was VFR."))]);
+        assert_eq!(provider.metar("EHAM").await.unwrap().flight_rules, FlightRules::Ifr);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_stations_without_reports() {
+        let directory = weather_directory("missing");
+        let provider = LocalProvider::new(directory.clone());
+        let mut snapshots = HashMap::new();
+
+        assert!(check_weather_alerts(&provider, &[alert(1, "EGLL", "rules")], &mut snapshots).await.is_empty());
+        assert!(snapshots.is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
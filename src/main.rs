@@ -5,7 +5,7 @@ mod models;
 use std::{
     collections::HashSet,
     env,
-    sync::Arc,
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
     time::Instant
 };
 use serenity::{async_trait, client::bridge::gateway::ShardManager, framework::{
@@ -18,7 +18,7 @@ use serenity::{async_trait, client::bridge::gateway::ShardManager, framework::{
             hook,
         }
     },
-}, http::Http, model::{event::ResumedEvent, gateway::Ready}, prelude::*};
+}, http::Http, model::{event::{MessageUpdateEvent, ResumedEvent}, gateway::Ready, id::MessageId, interactions::Interaction}, prelude::*};
 use tracing::{error, info};
 use tracing_subscriber::{
     FmtSubscriber,
@@ -29,10 +29,15 @@ use commands::{
     moderation::*,
     configuration::*,
     aviation::*,
+    settings::*,
+    airport_database::*,
+    logbook::*,
+    counting::*,
 };
 
 use helpers::global_data::Database;
 use mongodb::Client as MongoClient;
+use mongodb::bson::{doc, Bson};
 use mongodb::options::ClientOptions as MongoClientOptions;
 
 use serenity::client::bridge::gateway::GatewayIntents;
@@ -40,8 +45,15 @@ use serenity::framework::standard::{CommandResult, HelpOptions, Args, CommandGro
 use serenity::model::channel::Message;
 use serenity::model::id::{UserId, ChannelId, GuildId};
 use serenity::model::guild::{Guild, GuildUnavailable};
-use crate::helpers::database_helper::DatabaseGuild;
-use crate::helpers::global_data::{Uptime, CountingCache, PrefixCache, ReqwestContainer};
+use crate::helpers::database_helper::{DatabaseGuild, DatabaseCountingStats, GuildCounting, WeatherAlert};
+use crate::helpers::global_data::{Uptime, CountingCache, PrefixCache, ReqwestContainer, AviationProviderContainer, AviationCache, WeatherAlertCache, NotamSourceContainer, AirportDatabaseContainer};
+use crate::helpers::weather_alerts::run_weather_alerts;
+use crate::helpers::counting::{handle_counting_message, handle_counting_edit, handle_counting_delete, handle_counting_delete_bulk};
+use crate::commands::slash::{register_slash_commands, handle_interaction};
+use crate::helpers::notam_source::notam_source_from_env;
+use crate::helpers::airport_database::{AirportDatabase, AirportDatabaseError};
+use crate::helpers::aviation_provider::{provider_from_env, LOCAL_PROVIDER_NAME};
+use crate::helpers::response_cache::{ResponseCache, CachingProvider};
 use serenity::futures::StreamExt;
 use dashmap::DashMap;
 
@@ -54,7 +66,10 @@ impl TypeMapKey for ShardManagerContainer {
     type Value = Arc<Mutex<ShardManager>>;
 }
 
-struct Handler;
+struct Handler {
+    weather_alerts_running: AtomicBool,
+    slash_commands_registered: AtomicBool,
+}
 
 #[async_trait]
 impl EventHandler for Handler {
@@ -65,61 +80,60 @@ impl EventHandler for Handler {
                 if document.is_some() {
                     // Remove from cache
                     let database_guild = bson::from_document::<DatabaseGuild>(document.unwrap()).unwrap();
-                    if database_guild.counting.is_some() {
-                        ctx.data.read().await.get::<CountingCache>().unwrap().remove(&ChannelId::from(database_guild.counting.unwrap().channel as u64));
+                    for counting in &database_guild.counting {
+                        ctx.data.read().await.get::<CountingCache>().unwrap().remove(&ChannelId::from(counting.channel as u64));
                     }
                     if database_guild.prefix.is_some() {
                         ctx.data.read().await.get::<PrefixCache>().unwrap().remove(&_incomplete.id);
                     }
+                    if !database_guild.weather_alerts.is_empty() {
+                        ctx.data.read().await.get::<WeatherAlertCache>().unwrap().remove(&_incomplete.id);
+                    }
                 }
             },
             Err(why) => error!("Error when deleting guild from database: {}", why),
         }
+
+        // Outages remove guilds too, The counting stats are only deleted when the bot really left
+        if !_incomplete.unavailable {
+            DatabaseCountingStats::delete_for_guild(&ctx, _incomplete.id.0 as i64).await;
+        }
     }
 
     async fn message(&self, ctx: Context, msg: Message) {
-        // Counting channel
-        let counting_cache = ctx.data.read().await.get::<CountingCache>().cloned().unwrap();
-        // The number is gotten like this so the reference is dropped.
-        let number: Option<i64> = {
-            if let Some(counting_map) = counting_cache.get(&msg.channel_id) {
-                Some(*counting_map.value())
-            } else {
-                None
-            }
-        };
-        if let Some(number) = number {
-            // Parse the message content into an i64, Otherwise delete the message.
-            if let Ok(new_number) = msg.content.parse::<i64>() {
-                if new_number == number + 1 {
-                    // Insert the new number into the cache
-                    counting_cache.insert(msg.channel_id, new_number);
-
-                    // Edit the database to show the right number
-                    let mut database_guild = DatabaseGuild::get_or_insert_new(&ctx, msg.guild_id.unwrap().0 as i64).await;
-                    // Stupid hacky stuff
-                    let mut new_counting = database_guild.counting.unwrap();
-                    new_counting.count += 1;
-                    database_guild.counting.replace(new_counting);
-
-                    DatabaseGuild::insert_or_replace(&ctx, database_guild).await;
-                } else {
-                    // Delete the message if it's not the correct number
-                    match msg.delete(&ctx).await {
-                        _ => {}
-                    };
-                }
-            } else {
-                // Delete the message if it couldn't parse as a number
-                match msg.delete(&ctx).await {
-                    _ => {}
-                }
+        handle_counting_message(&ctx, &msg).await;
+    }
+
+    async fn message_update(&self, ctx: Context, _old_if_available: Option<Message>, _new: Option<Message>, event: MessageUpdateEvent) {
+        handle_counting_edit(&ctx, &event).await;
+    }
+
+    async fn message_delete(&self, ctx: Context, channel_id: ChannelId, deleted_message_id: MessageId, guild_id: Option<GuildId>) {
+        handle_counting_delete(&ctx, channel_id, deleted_message_id, guild_id).await;
+    }
+
+    async fn message_delete_bulk(&self, ctx: Context, channel_id: ChannelId, multiple_deleted_messages_ids: Vec<MessageId>, guild_id: Option<GuildId>) {
+        handle_counting_delete_bulk(&ctx, channel_id, &multiple_deleted_messages_ids, guild_id).await;
+    }
+
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!("Connected as {}", ready.user.name);
+
+        // Every shard calls this, But the weather alerts should only be polled once
+        if !self.weather_alerts_running.swap(true, Ordering::SeqCst) {
+            tokio::spawn(run_weather_alerts(ctx.clone()));
+        }
+
+        // The slash commands only have to be registered once too
+        if !self.slash_commands_registered.swap(true, Ordering::SeqCst) {
+            if let Err(why) = register_slash_commands(&ctx).await {
+                error!("Error registering the slash commands: {:?}", why);
             }
         }
     }
 
-    async fn ready(&self, _: Context, ready: Ready) {
-        info!("Connected as {}", ready.user.name);
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        handle_interaction(&ctx, interaction).await;
     }
 
     async fn resume(&self, _: Context, _: ResumedEvent) {
@@ -137,13 +151,37 @@ struct Moderation;
 
 #[group]
 #[prefixes("config", "configure", "conf")]
-#[commands(prefix, count)]
+#[commands(prefix, count, wxalert)]
 struct Configuration;
 
 #[group]
-#[commands(icao)]
+#[commands(icao, metar, taf, winds, route, nearest, notam, da, daylight)]
 struct Aviation;
 
+#[group]
+#[prefixes("settings", "preferences", "prefs")]
+#[default_command(show)]
+#[commands(show, units, home)]
+struct Settings;
+
+#[group]
+#[owners_only]
+#[prefixes("airportdb", "airports")]
+#[commands(import, refresh)]
+struct Airports;
+
+#[group]
+#[prefixes("log", "logbook")]
+#[default_command(log_list)]
+#[commands(log_add, log_list, log_totals, log_export)]
+struct Logbook;
+
+#[group]
+#[prefixes("count", "counting")]
+#[default_command(leaderboard)]
+#[commands(leaderboard, stats)]
+struct Counting;
+
 #[help]
 #[individual_command_tip =
 "Hello!
@@ -276,13 +314,16 @@ async fn main() {
         .group(&MODERATION_GROUP)
         .group(&CONFIGURATION_GROUP)
         .group(&AVIATION_GROUP)
+        .group(&SETTINGS_GROUP)
+        .group(&AIRPORTS_GROUP)
+        .group(&LOGBOOK_GROUP)
+        .group(&COUNTING_GROUP)
         .help(&MY_HELP);
 
-    //let avwx_token = env::var("AVWX_TOKEN").unwrap();
-
     let mut client = Client::builder(&token)
+        .application_id(_bot_id.0)
         .framework(framework)
-        .event_handler(Handler)
+        .event_handler(Handler { weather_alerts_running: AtomicBool::new(false), slash_commands_registered: AtomicBool::new(false) })
         .intents(
             GatewayIntents::GUILD_MESSAGES |
             GatewayIntents::GUILDS |
@@ -312,29 +353,79 @@ async fn main() {
         };
         data.insert::<Database>(mongo_client);
 
-        let counting_cache: DashMap<ChannelId, i64> = DashMap::new();
+        let counting_cache: DashMap<ChannelId, GuildCounting> = DashMap::new();
         let prefix_cache: DashMap<GuildId, String> = DashMap::new();
+        let weather_alert_cache: DashMap<GuildId, Vec<WeatherAlert>> = DashMap::new();
         // Iterate through every guild in the database
-        let mut database_guilds_cursor = data.get::<Database>().unwrap().database(&mongo_database).collection("guilds").find(None, None).await.unwrap();
+        let database_guilds = data.get::<Database>().unwrap().database(&mongo_database).collection("guilds");
+        let mut database_guilds_cursor = database_guilds.find(None, None).await.unwrap();
         while let Some(document) = database_guilds_cursor.next().await {
-            let database_guild = bson::from_document::<DatabaseGuild>(document.unwrap()).unwrap();
+            let document = document.unwrap();
+            let database_guild = bson::from_document::<DatabaseGuild>(document.clone()).unwrap();
+            // Guilds from when there was one counting channel get a list, The counts are updated inside it
+            if let Some(Bson::Document(_)) = document.get("counting") {
+                let update = doc! { "$set": { "counting": bson::to_bson(&database_guild.counting).unwrap() } };
+                database_guilds.update_one(doc! { "_id": database_guild._id }, update, None).await.unwrap();
+            }
             if database_guild.prefix.is_some() {
                 prefix_cache.insert(GuildId::from(database_guild._id as u64), database_guild.prefix.unwrap());
             }
-            if database_guild.counting.is_some() {
-                counting_cache.insert(ChannelId::from(database_guild.counting.unwrap().channel as u64), database_guild.counting.unwrap().count);
+            for counting in database_guild.counting {
+                counting_cache.insert(ChannelId::from(counting.channel as u64), counting);
+            }
+            if !database_guild.weather_alerts.is_empty() {
+                weather_alert_cache.insert(GuildId::from(database_guild._id as u64), database_guild.weather_alerts);
             }
         }
 
         // Insert the DashMaps
         data.insert::<CountingCache>(Arc::from(counting_cache));
         data.insert::<PrefixCache>(Arc::from(prefix_cache));
+        data.insert::<WeatherAlertCache>(Arc::from(weather_alert_cache));
+
+        // Load the OurAirports dataset, The aviation commands work without it
+        let airport_database = match AirportDatabase::load_from_env().await {
+            Ok(airport_database) => airport_database,
+            Err(AirportDatabaseError::NotConfigured) => AirportDatabase::default(),
+            Err(why) => {
+                error!("Couldn't load the airport database: {}", why);
+                AirportDatabase::default()
+            }
+        };
+        data.insert::<AirportDatabaseContainer>(Arc::new(RwLock::new(airport_database)));
 
         // Insert uptime to global data
         data.insert::<Uptime>(Instant::now());
 
         // Insert a new Reqwest client
-        data.insert::<ReqwestContainer>(ReqwestClient::builder().redirect(Policy::none()).build().unwrap());
+        let reqwest_client = ReqwestClient::builder().redirect(Policy::none()).build().unwrap();
+        data.insert::<ReqwestContainer>(reqwest_client.clone());
+
+        // Insert the aviation data provider chosen for this deployment, Behind the response cache
+        let aviation_cache = Arc::new(ResponseCache::default());
+        data.insert::<AviationCache>(aviation_cache.clone());
+        // NOTAMs are optional, So a broken setup only turns them off
+        let notam_source = match notam_source_from_env(reqwest_client.clone()) {
+            Ok(notam_source) => notam_source,
+            Err(why) => {
+                error!("Couldn't set up the NOTAM source: {}", why);
+                None
+            }
+        };
+        data.insert::<NotamSourceContainer>(notam_source);
+        let aviation_provider = match provider_from_env(reqwest_client) {
+            Ok(aviation_provider) => aviation_provider,
+            Err(why) => {
+                error!("Couldn't set up the aviation provider: {}", why);
+                return;
+            }
+        };
+        // Local reports are read again every time, So edits to them show up right away
+        if aviation_provider.name() == LOCAL_PROVIDER_NAME {
+            data.insert::<AviationProviderContainer>(aviation_provider);
+        } else {
+            data.insert::<AviationProviderContainer>(Arc::new(CachingProvider::new(aviation_provider, aviation_cache)));
+        }
     }
 
     if let Err(why) = client.start_autosharded().await {
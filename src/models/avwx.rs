@@ -8,12 +8,11 @@ pub struct AvwxError {
     pub timestamp: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct AvwxIcao {
     pub city: String,
     pub country: String,
     pub elevation_ft: i32,
-    pub elevation_m: i32,
     pub iata: String,
     pub icao: String,
     pub latitude: f32,
@@ -21,7 +20,6 @@ pub struct AvwxIcao {
     pub name: String,
     pub note: Option<String>,
     pub reporting: bool,
-    pub state: String,
     #[serde(alias = "type")]
     pub airport_type: String,
     pub website: Option<String>,
@@ -30,6 +28,11 @@ pub struct AvwxIcao {
 }
 
 #[derive(Deserialize, Debug)]
+pub struct AvwxNearStation {
+    pub station: AvwxIcao,
+}
+
+#[derive(Deserialize, Debug, Clone)]
 pub struct AvwxIcaoRunway {
     pub length_ft: i32,
     pub width_ft: i32,
@@ -39,4 +42,82 @@ pub struct AvwxIcaoRunway {
     pub ident2: String,
     pub bearing1: f32,
     pub bearing2: f32,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxMetar {
+    pub raw: String,
+    pub station: String,
+    pub time: AvwxTimestamp,
+    pub flight_rules: String,
+    pub wind_direction: Option<AvwxNumber>,
+    pub wind_speed: Option<AvwxNumber>,
+    pub wind_gust: Option<AvwxNumber>,
+    pub visibility: Option<AvwxNumber>,
+    pub clouds: Vec<AvwxCloud>,
+    pub temperature: Option<AvwxNumber>,
+    pub dewpoint: Option<AvwxNumber>,
+    pub altimeter: Option<AvwxNumber>,
+    pub wx_codes: Vec<AvwxCode>,
+    pub units: AvwxUnits,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxNumber {
+    pub repr: String,
+    pub value: Option<f32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxTimestamp {
+    pub dt: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxCloud {
+    #[serde(alias = "type")]
+    pub cloud_type: String,
+    pub altitude: Option<i32>,
+    pub modifier: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxCode {
+    pub repr: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxUnits {
+    pub altimeter: String,
+    pub temperature: String,
+    pub visibility: String,
+    pub wind_speed: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxTaf {
+    pub raw: String,
+    pub station: String,
+    pub time: AvwxTimestamp,
+    pub start_time: AvwxTimestamp,
+    pub end_time: AvwxTimestamp,
+    pub forecast: Vec<AvwxTafForecast>,
+    pub units: AvwxUnits,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AvwxTafForecast {
+    pub raw: String,
+    #[serde(alias = "type")]
+    pub forecast_type: String,
+    pub start_time: AvwxTimestamp,
+    pub end_time: AvwxTimestamp,
+    pub probability: Option<AvwxNumber>,
+    pub flight_rules: String,
+    pub wind_direction: Option<AvwxNumber>,
+    pub wind_speed: Option<AvwxNumber>,
+    pub wind_gust: Option<AvwxNumber>,
+    pub visibility: Option<AvwxNumber>,
+    pub clouds: Vec<AvwxCloud>,
+    pub wx_codes: Vec<AvwxCode>,
+}
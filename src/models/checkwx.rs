@@ -0,0 +1,66 @@
+use serde::Deserialize;
+use crate::models::avwx::AvwxIcao;
+
+#[derive(Deserialize, Debug)]
+pub struct CheckWxResponse<T> {
+    pub data: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckWxError {
+    pub error: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckWxStation {
+    pub icao: String,
+    pub iata: Option<String>,
+    pub name: String,
+    pub city: Option<String>,
+    pub country: CheckWxCode,
+    pub elevation: Option<CheckWxElevation>,
+    pub latitude: CheckWxCoordinate,
+    pub longitude: CheckWxCoordinate,
+    #[serde(alias = "type")]
+    pub station_type: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckWxCode {
+    pub code: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckWxElevation {
+    pub feet: Option<f32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckWxCoordinate {
+    pub decimal: f32,
+}
+
+impl From<CheckWxStation> for AvwxIcao {
+    fn from(station: CheckWxStation) -> Self {
+        let elevation_ft = station.elevation.as_ref().and_then(|elevation| elevation.feet).unwrap_or(0.0);
+
+        AvwxIcao {
+            city: station.city.unwrap_or_default(),
+            country: station.country.code,
+            elevation_ft: elevation_ft.round() as i32,
+            iata: station.iata.unwrap_or_default(),
+            wiki: format!("https://checkwx.com/weather/{}", station.icao),
+            icao: station.icao,
+            latitude: station.latitude.decimal,
+            longitude: station.longitude.decimal,
+            name: station.name,
+            note: None,
+            // CheckWX only knows stations that have reports
+            reporting: true,
+            airport_type: station.station_type.unwrap_or_default(),
+            website: None,
+            // CheckWX doesn't have runway data
+            runways: Vec::new(),
+        }
+    }
+}
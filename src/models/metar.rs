@@ -0,0 +1,973 @@
+// Offline METAR and TAF decoder, Turns raw reports into the same structs the AVWX models get converted into.
+use std::fmt;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use crate::models::avwx::{AvwxMetar, AvwxTaf, AvwxNumber, AvwxCloud, AvwxTafForecast};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metar {
+    pub raw: String,
+    pub station: String,
+    pub time: Option<ReportTime>,
+    pub auto: bool,
+    pub conditions: Conditions,
+    pub temperature: Option<i32>,
+    pub dewpoint: Option<i32>,
+    pub altimeter: Option<Altimeter>,
+    pub trend: Option<String>,
+    pub remarks: Option<String>,
+    pub flight_rules: FlightRules,
+    pub unparsed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Taf {
+    pub raw: String,
+    pub station: String,
+    pub time: Option<ReportTime>,
+    pub valid: Option<Validity>,
+    pub periods: Vec<TafPeriod>,
+    pub remarks: Option<String>,
+    pub unparsed: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TafPeriod {
+    pub raw: String,
+    pub kind: ChangeKind,
+    pub valid: Option<Validity>,
+    pub conditions: Conditions,
+    // Worked out on the conditions merged with the prevailing ones, A TEMPO group only lists what changes.
+    pub flight_rules: FlightRules,
+}
+
+/// Weather elements that are shared between a METAR and a TAF period.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Conditions {
+    pub wind: Option<Wind>,
+    pub visibility: Option<Visibility>,
+    pub cavok: bool,
+    pub weather: Vec<String>,
+    pub clouds: Vec<CloudLayer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReportTime {
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Validity {
+    pub from: ReportTime,
+    pub to: ReportTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wind {
+    /// `None` when the direction is variable.
+    pub direction: Option<u16>,
+    pub speed: u16,
+    pub gust: Option<u16>,
+    pub unit: SpeedUnit,
+    pub variable_from: Option<(u16, u16)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedUnit {
+    Knots,
+    MetersPerSecond,
+    KilometersPerHour,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Visibility {
+    pub value: f32,
+    pub unit: DistanceUnit,
+    pub modifier: Option<VisibilityModifier>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistanceUnit {
+    Meters,
+    StatuteMiles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VisibilityModifier {
+    LessThan,
+    GreaterThan,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloudLayer {
+    pub coverage: CloudCoverage,
+    /// Height above ground in feet.
+    pub altitude: Option<u32>,
+    pub modifier: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CloudCoverage {
+    Few,
+    Scattered,
+    Broken,
+    Overcast,
+    VerticalVisibility,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Altimeter {
+    Hectopascals(f32),
+    InchesOfMercury(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Base,
+    From,
+    Becoming,
+    Temporary,
+    Probability(u8),
+    ProbabilityTemporary(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FlightRules {
+    Vfr,
+    Mvfr,
+    Ifr,
+    Lifr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportParseError {
+    Empty,
+    MissingStation,
+}
+
+impl fmt::Display for ReportParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportParseError::Empty => write!(f, "The report is empty."),
+            ReportParseError::MissingStation => write!(f, "The report doesn't start with a valid station ident."),
+        }
+    }
+}
+
+impl std::error::Error for ReportParseError {}
+
+impl Metar {
+    pub fn parse(raw: &str) -> Result<Metar, ReportParseError> {
+        let raw = normalize_report(raw);
+        let mut tokens = raw.split(' ').peekable();
+
+        // Skip the report type
+        while let Some(&token) = tokens.peek() {
+            if token == "METAR" || token == "SPECI" || token == "COR" { tokens.next(); } else { break; }
+        }
+
+        let station = parse_station(tokens.next())?;
+        let mut metar = Metar {
+            raw: raw.clone(),
+            station,
+            time: None,
+            auto: false,
+            conditions: Conditions::default(),
+            temperature: None,
+            dewpoint: None,
+            altimeter: None,
+            trend: None,
+            remarks: None,
+            flight_rules: FlightRules::Vfr,
+            unparsed: Vec::new(),
+        };
+
+        let tokens: Vec<&str> = tokens.collect();
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = tokens[index];
+
+            if token == "RMK" {
+                metar.remarks = Some(tokens[index + 1..].join(" "));
+                break;
+            } else if token == "NOSIG" || token == "BECMG" || token == "TEMPO" {
+                metar.trend = Some(tokens[index..].join(" "));
+                break;
+            } else if metar.time.is_none() && token.ends_with('Z') && token.len() == 7 {
+                metar.time = parse_day_time(&token[..6]);
+            } else if token == "AUTO" {
+                metar.auto = true;
+            } else if token == "COR" || token == "NIL" {
+                // Nothing to decode
+            } else if let Some(consumed) = parse_condition_token(&mut metar.conditions, &tokens[index..]) {
+                index += consumed;
+                continue;
+            } else if let Some((temperature, dewpoint)) = parse_temperatures(token) {
+                metar.temperature = temperature;
+                metar.dewpoint = dewpoint;
+            } else if let Some(altimeter) = parse_altimeter(token) {
+                metar.altimeter = Some(altimeter);
+            } else if is_runway_visual_range(token) {
+                // Runway visual range isn't decoded
+            } else {
+                metar.unparsed.push(token.to_string());
+            }
+
+            index += 1;
+        }
+
+        metar.flight_rules = metar.conditions.flight_rules();
+        Ok(metar)
+    }
+}
+
+impl Taf {
+    pub fn parse(raw: &str) -> Result<Taf, ReportParseError> {
+        let raw = normalize_report(raw);
+        let mut tokens = raw.split(' ').peekable();
+
+        // Skip the report type and amendment markers
+        while let Some(&token) = tokens.peek() {
+            if token == "TAF" || token == "AMD" || token == "COR" { tokens.next(); } else { break; }
+        }
+
+        let station = parse_station(tokens.next())?;
+        let mut taf = Taf {
+            raw: raw.clone(),
+            station,
+            time: None,
+            valid: None,
+            periods: Vec::new(),
+            remarks: None,
+            unparsed: Vec::new(),
+        };
+
+        let tokens: Vec<&str> = tokens.collect();
+        let mut index = 0;
+        // The header carries the issue time and the validity of the whole report
+        while index < tokens.len() {
+            let token = tokens[index];
+            if taf.time.is_none() && token.ends_with('Z') && token.len() == 7 {
+                taf.time = parse_day_time(&token[..6]);
+            } else if let Some(validity) = parse_validity(token) {
+                taf.valid = Some(validity);
+                index += 1;
+                break;
+            } else {
+                break;
+            }
+            index += 1;
+        }
+
+        // Split the rest of the report into change groups
+        let mut groups: Vec<(ChangeKind, Option<Validity>, Vec<&str>)> = vec![(ChangeKind::Base, taf.valid, Vec::new())];
+        while index < tokens.len() {
+            let token = tokens[index];
+
+            if token == "RMK" {
+                taf.remarks = Some(tokens[index + 1..].join(" "));
+                break;
+            } else if token.starts_with("FM") && token.len() == 8 {
+                match parse_day_time(&token[2..]) {
+                    Some(from) => groups.push((ChangeKind::From, Some(Validity { from, to: from }), vec![token])),
+                    None => groups.last_mut().unwrap().2.push(token),
+                }
+            } else if token == "BECMG" || token == "TEMPO" {
+                let kind = if token == "BECMG" { ChangeKind::Becoming } else { ChangeKind::Temporary };
+                groups.push((kind, None, vec![token]));
+            } else if let Some(probability) = token.strip_prefix("PROB") {
+                let probability = probability.parse::<u8>().unwrap_or(0);
+                if tokens.get(index + 1) == Some(&"TEMPO") {
+                    groups.push((ChangeKind::ProbabilityTemporary(probability), None, vec![token, "TEMPO"]));
+                    index += 1;
+                } else {
+                    groups.push((ChangeKind::Probability(probability), None, vec![token]));
+                }
+            } else if let Some(validity) = parse_validity(token) {
+                let group = groups.last_mut().unwrap();
+                if group.1.is_none() { group.1 = Some(validity); }
+                group.2.push(token);
+            } else {
+                groups.last_mut().unwrap().2.push(token);
+            }
+
+            index += 1;
+        }
+
+        // FM groups last until the next FM group or the end of the report
+        let mut from_indexes = groups.iter().enumerate()
+            .filter(|(_, group)| group.0 == ChangeKind::From || group.0 == ChangeKind::Base)
+            .map(|(group_index, _)| group_index).collect::<Vec<usize>>();
+        from_indexes.push(groups.len());
+        for window in from_indexes.windows(2) {
+            let next_from = groups.get(window[1]).and_then(|group| group.1).map(|validity| validity.from);
+            if let (Some(validity), Some(end)) = (groups[window[0]].1.as_mut(), next_from.or(taf.valid.map(|validity| validity.to))) {
+                validity.to = end;
+            }
+        }
+
+        // Decode every group, Keeping track of the prevailing conditions for the flight rules
+        let mut prevailing = Conditions::default();
+        for (kind, valid, group_tokens) in groups {
+            let mut conditions = Conditions::default();
+            let mut group_index = 0;
+            while group_index < group_tokens.len() {
+                let token = group_tokens[group_index];
+                let is_group_marker = group_index == 0 && (kind != ChangeKind::Base);
+                if is_group_marker || token == "TEMPO" || parse_validity(token).is_some() {
+                    group_index += 1;
+                    continue;
+                }
+
+                if let Some(consumed) = parse_condition_token(&mut conditions, &group_tokens[group_index..]) {
+                    group_index += consumed;
+                } else {
+                    taf.unparsed.push(token.to_string());
+                    group_index += 1;
+                }
+            }
+
+            let flight_rules = match kind {
+                ChangeKind::Base | ChangeKind::From => {
+                    prevailing = conditions.clone();
+                    conditions.flight_rules()
+                }
+                ChangeKind::Becoming => {
+                    prevailing = conditions.merged_with(&prevailing);
+                    prevailing.flight_rules()
+                }
+                _ => conditions.merged_with(&prevailing).flight_rules(),
+            };
+
+            taf.periods.push(TafPeriod {
+                raw: group_tokens.join(" "),
+                kind,
+                valid,
+                conditions,
+                flight_rules,
+            });
+        }
+
+        // An empty base group means the report started with a FM group
+        if taf.periods.first().map(|period| period.raw.is_empty()).unwrap_or(false) {
+            taf.periods.remove(0);
+        }
+
+        Ok(taf)
+    }
+}
+
+impl Conditions {
+    /// The lowest broken, overcast or vertical visibility layer in feet.
+    pub fn ceiling(&self) -> Option<u32> {
+        self.clouds.iter()
+            .filter(|cloud| matches!(cloud.coverage, CloudCoverage::Broken | CloudCoverage::Overcast | CloudCoverage::VerticalVisibility))
+            .filter_map(|cloud| cloud.altitude)
+            .min()
+    }
+
+    pub fn flight_rules(&self) -> FlightRules {
+        if self.cavok {
+            return FlightRules::Vfr;
+        }
+
+        let visibility = self.visibility.map(|visibility| visibility.as_statute_miles()).unwrap_or(10.0);
+        let ceiling = self.ceiling().unwrap_or(u32::MAX);
+
+        if ceiling < 500 || visibility < 1.0 {
+            FlightRules::Lifr
+        } else if ceiling < 1000 || visibility < 3.0 {
+            FlightRules::Ifr
+        } else if ceiling <= 3000 || visibility <= 5.0 {
+            FlightRules::Mvfr
+        } else {
+            FlightRules::Vfr
+        }
+    }
+
+    /// Fills in everything this group doesn't report with the prevailing conditions.
+    pub fn merged_with(&self, prevailing: &Conditions) -> Conditions {
+        let reports_sky = self.cavok || !self.clouds.is_empty();
+        let mut merged = Conditions {
+            wind: self.wind.or(prevailing.wind),
+            visibility: self.visibility.or(prevailing.visibility),
+            cavok: self.cavok,
+            weather: if self.weather.is_empty() { prevailing.weather.clone() } else { self.weather.clone() },
+            clouds: if reports_sky { self.clouds.clone() } else { prevailing.clouds.clone() },
+        };
+        if !self.cavok && self.visibility.is_none() && !reports_sky {
+            merged.cavok = prevailing.cavok;
+        }
+
+        merged
+    }
+}
+
+impl Wind {
+    pub fn speed_in_knots(&self) -> f32 {
+        self.unit.to_knots(self.speed as f32)
+    }
+
+    pub fn gust_in_knots(&self) -> Option<f32> {
+        self.gust.map(|gust| self.unit.to_knots(gust as f32))
+    }
+}
+
+impl SpeedUnit {
+    pub fn to_knots(self, speed: f32) -> f32 {
+        match self {
+            SpeedUnit::Knots => speed,
+            SpeedUnit::MetersPerSecond => speed * 1.943_844,
+            SpeedUnit::KilometersPerHour => speed / 1.852,
+        }
+    }
+}
+
+impl Visibility {
+    pub fn as_statute_miles(&self) -> f32 {
+        match self.unit {
+            DistanceUnit::StatuteMiles => self.value,
+            DistanceUnit::Meters => self.value / 1609.344,
+        }
+    }
+}
+
+impl Altimeter {
+    pub fn as_hectopascals(&self) -> f32 {
+        match self {
+            Altimeter::Hectopascals(value) => *value,
+            Altimeter::InchesOfMercury(value) => value * 33.863_89,
+        }
+    }
+
+    pub fn as_inches_of_mercury(&self) -> f32 {
+        match self {
+            Altimeter::Hectopascals(value) => value / 33.863_89,
+            Altimeter::InchesOfMercury(value) => *value,
+        }
+    }
+}
+
+impl CloudCoverage {
+    pub fn abbreviation(self) -> &'static str {
+        match self {
+            CloudCoverage::Few => "FEW",
+            CloudCoverage::Scattered => "SCT",
+            CloudCoverage::Broken => "BKN",
+            CloudCoverage::Overcast => "OVC",
+            CloudCoverage::VerticalVisibility => "VV",
+        }
+    }
+
+    fn from_abbreviation(abbreviation: &str) -> Option<CloudCoverage> {
+        match abbreviation {
+            "FEW" => Some(CloudCoverage::Few),
+            "SCT" => Some(CloudCoverage::Scattered),
+            "BKN" => Some(CloudCoverage::Broken),
+            "OVC" => Some(CloudCoverage::Overcast),
+            "VV" => Some(CloudCoverage::VerticalVisibility),
+            _ => None,
+        }
+    }
+}
+
+impl ChangeKind {
+    pub fn name(self) -> String {
+        match self {
+            ChangeKind::Base => String::from("Base forecast"),
+            ChangeKind::From => String::from("FM"),
+            ChangeKind::Becoming => String::from("BECMG"),
+            ChangeKind::Temporary => String::from("TEMPO"),
+            ChangeKind::Probability(probability) => format!("PROB{}", probability),
+            ChangeKind::ProbabilityTemporary(probability) => format!("PROB{} TEMPO", probability),
+        }
+    }
+}
+
+impl fmt::Display for FlightRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlightRules::Vfr => write!(f, "VFR"),
+            FlightRules::Mvfr => write!(f, "MVFR"),
+            FlightRules::Ifr => write!(f, "IFR"),
+            FlightRules::Lifr => write!(f, "LIFR"),
+        }
+    }
+}
+
+impl FlightRules {
+    pub fn from_name(name: &str) -> Option<FlightRules> {
+        match name.to_uppercase().as_str() {
+            "VFR" => Some(FlightRules::Vfr),
+            "MVFR" => Some(FlightRules::Mvfr),
+            "IFR" => Some(FlightRules::Ifr),
+            "LIFR" => Some(FlightRules::Lifr),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ReportTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}/{:02}:{:02}Z", self.day, self.hour, self.minute)
+    }
+}
+
+impl ReportTime {
+    /// Reports only carry the day of the month, So this assumes the report is from the last month when the day is ahead of `now`.
+    pub fn to_datetime(self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let (year, month) = if self.day > now.day() + 1 {
+            if now.month() == 1 { (now.year() - 1, 12) } else { (now.year(), now.month() - 1) }
+        } else {
+            (now.year(), now.month())
+        };
+
+        // 24Z is used for the end of a day in TAFs
+        let midnight = Utc.with_ymd_and_hms(year, month, self.day, 0, 0, 0).single()?;
+        Some(midnight + Duration::hours(self.hour as i64) + Duration::minutes(self.minute as i64))
+    }
+
+    // AVWX gives ISO 8601 timestamps like 2021-02-18T12:00:00Z
+    fn from_iso(timestamp: &str) -> Option<ReportTime> {
+        Some(ReportTime {
+            day: timestamp.get(8..10)?.parse().ok()?,
+            hour: timestamp.get(11..13)?.parse().ok()?,
+            minute: timestamp.get(14..16)?.parse().ok()?,
+        })
+    }
+}
+
+/// Decodes a present weather code like `-SHRA` into words.
+pub fn describe_weather(code: &str) -> String {
+    let mut words = Vec::new();
+    let mut rest = code;
+
+    if let Some(stripped) = rest.strip_prefix('-') { words.push("Light"); rest = stripped; }
+    else if let Some(stripped) = rest.strip_prefix('+') { words.push("Heavy"); rest = stripped; }
+    if let Some(stripped) = rest.strip_prefix("VC") { words.push("Nearby"); rest = stripped; }
+
+    while rest.len() >= 2 {
+        // Slicing by bytes would split letters like É, Those aren't weather anyway
+        let word = match rest.get(..2).unwrap_or_default() {
+            "MI" => "Shallow", "PR" => "Partial", "BC" => "Patches", "DR" => "Low drifting",
+            "BL" => "Blowing", "SH" => "Showers", "TS" => "Thunderstorm", "FZ" => "Freezing",
+            "DZ" => "Drizzle", "RA" => "Rain", "SN" => "Snow", "SG" => "Snow grains",
+            "IC" => "Ice crystals", "PL" => "Ice pellets", "GR" => "Hail", "GS" => "Small hail",
+            "UP" => "Unknown precipitation", "BR" => "Mist", "FG" => "Fog", "FU" => "Smoke",
+            "VA" => "Volcanic ash", "DU" => "Dust", "SA" => "Sand", "HZ" => "Haze",
+            "PY" => "Spray", "PO" => "Dust whirls", "SQ" => "Squalls", "FC" => "Funnel cloud",
+            "SS" => "Sandstorm", "DS" => "Duststorm",
+            _ => return code.to_string(),
+        };
+        words.push(word);
+        rest = &rest[2..];
+    }
+
+    words.join(" ")
+}
+
+impl From<&AvwxMetar> for Metar {
+    fn from(avwx_metar: &AvwxMetar) -> Self {
+        let units = &avwx_metar.units;
+        let conditions = Conditions {
+            wind: avwx_wind(&avwx_metar.wind_direction, &avwx_metar.wind_speed, &avwx_metar.wind_gust, &units.wind_speed),
+            visibility: avwx_visibility(&avwx_metar.visibility, &units.visibility),
+            cavok: avwx_metar.raw.contains("CAVOK"),
+            weather: avwx_metar.wx_codes.iter().map(|code| code.repr.clone()).collect(),
+            clouds: avwx_clouds(&avwx_metar.clouds),
+        };
+
+        // Temperatures are kept in celsius
+        let to_celsius = |number: &Option<AvwxNumber>| number.as_ref().and_then(|number| number.value).map(|value| {
+            if units.temperature == "F" { ((value - 32.0) * 5.0 / 9.0).round() as i32 } else { value.round() as i32 }
+        });
+
+        Metar {
+            raw: avwx_metar.raw.clone(),
+            station: avwx_metar.station.clone(),
+            time: ReportTime::from_iso(&avwx_metar.time.dt),
+            auto: avwx_metar.raw.split(' ').any(|token| token == "AUTO"),
+            conditions,
+            temperature: to_celsius(&avwx_metar.temperature),
+            dewpoint: to_celsius(&avwx_metar.dewpoint),
+            altimeter: avwx_metar.altimeter.as_ref().and_then(|altimeter| altimeter.value).map(|value| {
+                if units.altimeter == "inHg" { Altimeter::InchesOfMercury(value) } else { Altimeter::Hectopascals(value) }
+            }),
+            trend: None,
+            remarks: None,
+            flight_rules: FlightRules::from_name(&avwx_metar.flight_rules).unwrap_or(FlightRules::Vfr),
+            unparsed: Vec::new(),
+        }
+    }
+}
+
+impl From<&AvwxTaf> for Taf {
+    fn from(avwx_taf: &AvwxTaf) -> Self {
+        let units = &avwx_taf.units;
+        let periods = avwx_taf.forecast.iter().map(|forecast: &AvwxTafForecast| {
+            let probability = forecast.probability.as_ref().and_then(|probability| probability.value).unwrap_or(0.0) as u8;
+            let kind = match forecast.forecast_type.as_str() {
+                "BECMG" => ChangeKind::Becoming,
+                "TEMPO" if probability > 0 => ChangeKind::ProbabilityTemporary(probability),
+                "TEMPO" | "INTER" => ChangeKind::Temporary,
+                "PROB" => ChangeKind::Probability(probability),
+                _ => ChangeKind::From,
+            };
+
+            TafPeriod {
+                raw: forecast.raw.clone(),
+                kind,
+                valid: ReportTime::from_iso(&forecast.start_time.dt)
+                    .and_then(|from| ReportTime::from_iso(&forecast.end_time.dt).map(|to| Validity { from, to })),
+                conditions: Conditions {
+                    wind: avwx_wind(&forecast.wind_direction, &forecast.wind_speed, &forecast.wind_gust, &units.wind_speed),
+                    visibility: avwx_visibility(&forecast.visibility, &units.visibility),
+                    cavok: forecast.raw.contains("CAVOK"),
+                    weather: forecast.wx_codes.iter().map(|code| code.repr.clone()).collect(),
+                    clouds: avwx_clouds(&forecast.clouds),
+                },
+                flight_rules: FlightRules::from_name(&forecast.flight_rules).unwrap_or(FlightRules::Vfr),
+            }
+        }).collect();
+
+        Taf {
+            raw: avwx_taf.raw.clone(),
+            station: avwx_taf.station.clone(),
+            time: ReportTime::from_iso(&avwx_taf.time.dt),
+            valid: ReportTime::from_iso(&avwx_taf.start_time.dt)
+                .and_then(|from| ReportTime::from_iso(&avwx_taf.end_time.dt).map(|to| Validity { from, to })),
+            periods,
+            remarks: None,
+            unparsed: Vec::new(),
+        }
+    }
+}
+
+fn avwx_wind(direction: &Option<AvwxNumber>, speed: &Option<AvwxNumber>, gust: &Option<AvwxNumber>, unit: &str) -> Option<Wind> {
+    let speed = speed.as_ref().and_then(|speed| speed.value)?;
+
+    Some(Wind {
+        direction: direction.as_ref().and_then(|direction| direction.value).map(|direction| direction.round() as u16),
+        speed: speed.round() as u16,
+        gust: gust.as_ref().and_then(|gust| gust.value).map(|gust| gust.round() as u16),
+        unit: match unit {
+            "m/s" => SpeedUnit::MetersPerSecond,
+            "km/h" => SpeedUnit::KilometersPerHour,
+            _ => SpeedUnit::Knots,
+        },
+        variable_from: None,
+    })
+}
+
+fn avwx_visibility(visibility: &Option<AvwxNumber>, unit: &str) -> Option<Visibility> {
+    let visibility = visibility.as_ref()?;
+
+    Some(Visibility {
+        value: visibility.value?,
+        unit: if unit == "sm" { DistanceUnit::StatuteMiles } else { DistanceUnit::Meters },
+        modifier: match visibility.repr.chars().next() {
+            Some('M') => Some(VisibilityModifier::LessThan),
+            Some('P') => Some(VisibilityModifier::GreaterThan),
+            _ if visibility.repr == "9999" => Some(VisibilityModifier::GreaterThan),
+            _ => None,
+        },
+    })
+}
+
+fn avwx_clouds(clouds: &[AvwxCloud]) -> Vec<CloudLayer> {
+    clouds.iter().filter_map(|cloud| Some(CloudLayer {
+        coverage: CloudCoverage::from_abbreviation(&cloud.cloud_type)?,
+        // AVWX reports cloud altitudes in hundreds of feet
+        altitude: cloud.altitude.map(|altitude| altitude.max(0) as u32 * 100),
+        modifier: cloud.modifier.clone(),
+    })).collect()
+}
+
+// Uppercase with single spaces and without the trailing `=` some stations send.
+fn normalize_report(raw: &str) -> String {
+    raw.trim().trim_end_matches('=').split_whitespace().collect::<Vec<&str>>().join(" ").to_uppercase()
+}
+
+fn parse_station(token: Option<&str>) -> Result<String, ReportParseError> {
+    match token {
+        None | Some("") => Err(ReportParseError::Empty),
+        Some(token) if token.len() == 4 && token.chars().all(|c| c.is_ascii_alphanumeric()) => Ok(token.to_string()),
+        Some(_) => Err(ReportParseError::MissingStation),
+    }
+}
+
+// Parses DDHHMM
+fn parse_day_time(token: &str) -> Option<ReportTime> {
+    if token.len() != 6 || !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let time = ReportTime {
+        day: token[0..2].parse().ok()?,
+        hour: token[2..4].parse().ok()?,
+        minute: token[4..6].parse().ok()?,
+    };
+    if time.day == 0 || time.day > 31 || time.hour > 24 || time.minute > 59 { None } else { Some(time) }
+}
+
+// Parses DDHH/DDHH
+fn parse_validity(token: &str) -> Option<Validity> {
+    let (from, to) = token.split_once('/')?;
+    if from.len() != 4 || to.len() != 4 {
+        return None;
+    }
+
+    Some(Validity {
+        from: parse_day_time(&format!("{}00", from))?,
+        to: parse_day_time(&format!("{}00", to))?,
+    })
+}
+
+// Tries to decode wind, visibility, weather and clouds, Returns how many tokens were used.
+fn parse_condition_token(conditions: &mut Conditions, tokens: &[&str]) -> Option<usize> {
+    let token = tokens[0];
+
+    if let Some(wind) = parse_wind(token) {
+        conditions.wind = Some(wind);
+        // Variable wind direction like 180V240
+        if let Some(variable_from) = tokens.get(1).and_then(|next| parse_variable_direction(next)) {
+            conditions.wind.as_mut().unwrap().variable_from = Some(variable_from);
+            return Some(2);
+        }
+        return Some(1);
+    }
+    if token == "CAVOK" {
+        conditions.cavok = true;
+        conditions.visibility = Some(Visibility { value: 9999.0, unit: DistanceUnit::Meters, modifier: Some(VisibilityModifier::GreaterThan) });
+        return Some(1);
+    }
+    // Whole and fractional statute miles like 1 1/2SM
+    if let (true, Some(next)) = (token.len() == 1 && token.chars().all(|c| c.is_ascii_digit()), tokens.get(1)) {
+        if let Some(mut visibility) = parse_visibility(next).filter(|visibility| visibility.unit == DistanceUnit::StatuteMiles) {
+            visibility.value += token.parse::<f32>().ok()?;
+            conditions.visibility = Some(visibility);
+            return Some(2);
+        }
+    }
+    if let Some(visibility) = parse_visibility(token) {
+        if conditions.visibility.is_none() {
+            conditions.visibility = Some(visibility);
+        }
+        return Some(1);
+    }
+    if let Some(cloud) = parse_cloud(token) {
+        conditions.clouds.push(cloud);
+        return Some(1);
+    }
+    if token == "SKC" || token == "CLR" || token == "NSC" || token == "NCD" || token == "NSW" {
+        return Some(1);
+    }
+    if is_weather(token) {
+        conditions.weather.push(token.to_string());
+        return Some(1);
+    }
+
+    None
+}
+
+fn parse_wind(token: &str) -> Option<Wind> {
+    let (unit, body) = if let Some(body) = token.strip_suffix("KT") {
+        (SpeedUnit::Knots, body)
+    } else if let Some(body) = token.strip_suffix("MPS") {
+        (SpeedUnit::MetersPerSecond, body)
+    } else if let Some(body) = token.strip_suffix("KMH") {
+        (SpeedUnit::KilometersPerHour, body)
+    } else {
+        return None;
+    };
+    if body.len() < 5 {
+        return None;
+    }
+
+    let (direction, rest) = (body.get(..3)?, body.get(3..)?);
+    let direction = match direction {
+        "VRB" => None,
+        direction => Some(direction.parse::<u16>().ok().filter(|direction| *direction <= 360)?),
+    };
+    let (speed, gust) = match rest.split_once('G') {
+        Some((speed, gust)) => (speed, Some(gust.parse::<u16>().ok()?)),
+        None => (rest, None),
+    };
+
+    Some(Wind {
+        direction,
+        speed: speed.parse().ok()?,
+        gust,
+        unit,
+        variable_from: None,
+    })
+}
+
+fn parse_variable_direction(token: &str) -> Option<(u16, u16)> {
+    let (from, to) = token.split_once('V')?;
+    if from.len() != 3 || to.len() != 3 {
+        return None;
+    }
+
+    Some((from.parse().ok()?, to.parse().ok()?))
+}
+
+fn parse_visibility(token: &str) -> Option<Visibility> {
+    // Meters, Optionally with a direction like 4000NE
+    let digits = token.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 4 && (token.len() == 4 || token[4..].chars().all(|c| "NESW".contains(c)) || &token[4..] == "NDV") {
+        let value = token[..4].parse::<f32>().ok()?;
+        return Some(Visibility {
+            value,
+            unit: DistanceUnit::Meters,
+            modifier: if value >= 9999.0 { Some(VisibilityModifier::GreaterThan) } else { None },
+        });
+    }
+
+    // Statute miles like 10SM, 1/2SM, M1/4SM and P6SM
+    let mut body = token.strip_suffix("SM")?;
+    let mut modifier = None;
+    if let Some(stripped) = body.strip_prefix('M') { modifier = Some(VisibilityModifier::LessThan); body = stripped; }
+    else if let Some(stripped) = body.strip_prefix('P') { modifier = Some(VisibilityModifier::GreaterThan); body = stripped; }
+
+    let value = match body.split_once('/') {
+        Some((numerator, denominator)) => numerator.parse::<f32>().ok()? / denominator.parse::<f32>().ok().filter(|d| *d > 0.0)?,
+        None => body.parse::<f32>().ok()?,
+    };
+
+    Some(Visibility { value, unit: DistanceUnit::StatuteMiles, modifier })
+}
+
+fn parse_cloud(token: &str) -> Option<CloudLayer> {
+    let coverage_length = if token.starts_with("VV") { 2 } else { 3 };
+    let coverage = CloudCoverage::from_abbreviation(token.get(..coverage_length)?)?;
+    let altitude = token.get(coverage_length..coverage_length + 3)?;
+    let modifier = token.get(coverage_length + 3..).filter(|modifier| !modifier.is_empty() && *modifier != "///");
+
+    Some(CloudLayer {
+        coverage,
+        altitude: if altitude == "///" { None } else { Some(altitude.parse::<u32>().ok()? * 100) },
+        modifier: modifier.map(|modifier| modifier.to_string()),
+    })
+}
+
+// Parses 12/M03, M01/M05 and 20/ when the dewpoint is missing.
+fn parse_temperatures(token: &str) -> Option<(Option<i32>, Option<i32>)> {
+    let (temperature, dewpoint) = token.split_once('/')?;
+    let parse = |value: &str| -> Result<Option<i32>, ()> {
+        if value.is_empty() || value == "//" {
+            return Ok(None);
+        }
+        let (negative, digits) = match value.strip_prefix('M') {
+            Some(digits) => (true, digits),
+            None => (false, value),
+        };
+        if digits.len() != 2 { return Err(()); }
+        let number = digits.parse::<i32>().map_err(|_| ())?;
+        Ok(Some(if negative { -number } else { number }))
+    };
+
+    // The temperature itself has to be there
+    let temperature = parse(temperature).ok()??;
+    Some((Some(temperature), parse(dewpoint).ok()?))
+}
+
+fn parse_altimeter(token: &str) -> Option<Altimeter> {
+    let (prefix, digits) = (token.get(..1)?, token.get(1..)?);
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let value = digits.parse::<f32>().ok()?;
+    match prefix {
+        "Q" => Some(Altimeter::Hectopascals(value)),
+        "A" => Some(Altimeter::InchesOfMercury(value / 100.0)),
+        _ => None,
+    }
+}
+
+fn is_runway_visual_range(token: &str) -> bool {
+    token.starts_with('R') && token.len() > 3 && token.get(1..3).is_some_and(|digits| digits.chars().all(|c| c.is_ascii_digit())) && token.contains('/')
+}
+
+fn is_weather(token: &str) -> bool {
+    let mut rest = token.trim_start_matches(&['-', '+'][..]);
+    if let Some(stripped) = rest.strip_prefix("VC") { rest = stripped; }
+    if rest.is_empty() || !rest.len().is_multiple_of(2) {
+        return false;
+    }
+
+    let description = describe_weather(token);
+    description != token
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_metars() {
+        let metar = Metar::parse("METAR EGLL 181250Z 24015G25KT 200V280 9999 -SHRA FEW012 BKN025 14/09 Q1008 NOSIG=").unwrap();
+        assert_eq!(metar.station, "EGLL");
+        assert_eq!(metar.time, Some(ReportTime { day: 18, hour: 12, minute: 50 }));
+        assert_eq!(metar.conditions.wind, Some(Wind { direction: Some(240), speed: 15, gust: Some(25), unit: SpeedUnit::Knots, variable_from: Some((200, 280)) }));
+        assert_eq!(metar.conditions.weather, vec!["-SHRA"]);
+        assert_eq!(metar.conditions.ceiling(), Some(2500));
+        assert_eq!((metar.temperature, metar.dewpoint), (Some(14), Some(9)));
+        assert_eq!(metar.altimeter, Some(Altimeter::Hectopascals(1008.0)));
+        assert_eq!(metar.trend.as_deref(), Some("NOSIG"));
+        assert_eq!(metar.flight_rules, FlightRules::Mvfr);
+        assert!(metar.unparsed.is_empty());
+
+        let metar = Metar::parse("KJFK 181251Z 31012KT 10SM FEW050 SCT250 18/04 A3002 RMK AO2 SLP165 T01830039").unwrap();
+        assert_eq!(metar.altimeter, Some(Altimeter::InchesOfMercury(30.02)));
+        assert_eq!(metar.remarks.as_deref(), Some("AO2 SLP165 T01830039"));
+        assert_eq!(metar.flight_rules, FlightRules::Vfr);
+
+        let metar = Metar::parse("KSFO 181256Z 28016KT 1 1/2SM BR OVC004 12/11 A2992").unwrap();
+        assert_eq!(metar.conditions.visibility.map(|visibility| visibility.value), Some(1.5));
+        assert_eq!(metar.flight_rules, FlightRules::Lifr);
+
+        let metar = Metar::parse("EHAM 181225Z AUTO 22008KT 180V260 CAVOK M01/M05 Q1019 NOSIG").unwrap();
+        assert!(metar.auto && metar.conditions.cavok);
+        assert_eq!((metar.temperature, metar.dewpoint), (Some(-1), Some(-5)));
+
+        let metar = Metar::parse("UUEE 181230Z 03004MPS 2100 R06L/P2000 BR SCT006 OVC040 08/07 Q1021").unwrap();
+        assert_eq!(metar.conditions.wind.unwrap().unit, SpeedUnit::MetersPerSecond);
+        assert_eq!(metar.flight_rules, FlightRules::Ifr);
+        assert!(metar.unparsed.is_empty());
+    }
+
+    #[test]
+    fn parses_tafs() {
+        let taf = Taf::parse("TAF EGLL 181058Z 1812/1918 24012KT 9999 SCT030 TEMPO 1812/1818 25015G25KT 7000 -SHRA BKN014 \
+                              PROB30 TEMPO 1818/1822 4000 RA BKN008 BECMG 1900/1903 VRB03KT").unwrap();
+        assert_eq!(taf.valid, Some(Validity { from: ReportTime { day: 18, hour: 12, minute: 0 }, to: ReportTime { day: 19, hour: 18, minute: 0 } }));
+        let kinds = taf.periods.iter().map(|period| period.kind).collect::<Vec<ChangeKind>>();
+        assert_eq!(kinds, vec![ChangeKind::Base, ChangeKind::Temporary, ChangeKind::ProbabilityTemporary(30), ChangeKind::Becoming]);
+        let flight_rules = taf.periods.iter().map(|period| period.flight_rules).collect::<Vec<FlightRules>>();
+        assert_eq!(flight_rules, vec![FlightRules::Vfr, FlightRules::Mvfr, FlightRules::Ifr, FlightRules::Vfr]);
+        assert!(taf.unparsed.is_empty());
+
+        let taf = Taf::parse("TAF KJFK 181120Z 1812/1918 31012KT P6SM FEW050 FM182000 32008KT P6SM SKC FM190600 VRB03KT 3SM BR OVC008").unwrap();
+        assert_eq!(taf.periods.len(), 3);
+        assert_eq!(taf.periods[1].valid.map(|validity| validity.to), Some(ReportTime { day: 19, hour: 6, minute: 0 }));
+        assert_eq!(taf.periods[2].flight_rules, FlightRules::Ifr);
+    }
+
+    #[test]
+    fn rejects_reports_without_station() {
+        assert_eq!(Metar::parse("  "), Err(ReportParseError::Empty));
+        assert_eq!(Metar::parse("METAR 181250Z 24015KT"), Err(ReportParseError::MissingStation));
+    }
+
+    #[test]
+    fn non_ascii_tokens_dont_panic() {
+        for raw in ["EHAM É123", "EHAM ÉÉ1KT", "EHAM AÉB", "EHAM R1É/", "EHAM -ÉA", "EHAM 1ÉÉ1KT"] {
+            let metar = Metar::parse(raw).unwrap();
+            assert_eq!(metar.unparsed.len(), 1, "{}", raw);
+            assert!(Taf::parse(raw).is_ok());
+        }
+    }
+}
@@ -1 +1,6 @@
-pub mod avwx;
\ No newline at end of file
+pub mod avwx;
+pub mod metar;
+pub mod checkwx;
+pub mod units;
+pub mod notam;
+pub mod ourairports;
\ No newline at end of file
@@ -0,0 +1,140 @@
+use std::fmt;
+use serde::Deserialize;
+use crate::models::avwx::AvwxIcaoRunway;
+
+/// A NOTAM as the bot uses it, No matter which source it came from.
+#[derive(Debug, Clone)]
+pub struct Notam {
+    pub id: String,
+    pub text: String,
+    pub effective_start: Option<String>,
+    pub effective_end: Option<String>,
+    pub category: NotamCategory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotamCategory {
+    RunwayClosure,
+    NavaidOutage,
+    Obstacle,
+    Other,
+}
+
+impl fmt::Display for NotamCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotamCategory::RunwayClosure => write!(f, "Runway closures"),
+            NotamCategory::NavaidOutage => write!(f, "Navaid outages"),
+            NotamCategory::Obstacle => write!(f, "Obstacles"),
+            NotamCategory::Other => write!(f, "Other"),
+        }
+    }
+}
+
+const NAVAIDS: [&str; 11] = ["VOR", "DME", "NDB", "ILS", "LOC", "GP", "GS", "TACAN", "VORTAC", "MKR", "LLZ"];
+const OUT_OF_SERVICE: [&str; 4] = ["U/S", "OTS", "UNSERVICEABLE", "UNUSABLE"];
+
+impl Notam {
+    pub fn new(id: String, text: String, effective_start: Option<String>, effective_end: Option<String>) -> Notam {
+        let category = NotamCategory::classify(&text);
+
+        Notam { id, text, effective_start, effective_end, category }
+    }
+
+    /// The runway idents this NOTAM closes, Like `09` and `27` for `RWY 09/27 CLSD`.
+    pub fn closed_runways(&self) -> Vec<String> {
+        closed_runways(&words(&self.text))
+    }
+}
+
+impl NotamCategory {
+    /// Sorts a NOTAM by the contractions in its text, Anything unknown ends up as `Other`.
+    pub fn classify(text: &str) -> NotamCategory {
+        let words = words(text);
+        let has = |word: &str| words.iter().any(|candidate| candidate == word);
+
+        if !closed_runways(&words).is_empty() {
+            NotamCategory::RunwayClosure
+        } else if NAVAIDS.iter().any(|navaid| has(navaid)) && OUT_OF_SERVICE.iter().any(|status| has(status)) {
+            NotamCategory::NavaidOutage
+        } else if has("OBST") || has("OBSTACLE") || has("CRANE") || has("CRANES") {
+            NotamCategory::Obstacle
+        } else {
+            NotamCategory::Other
+        }
+    }
+}
+
+/// Whether a runway is closed by any of the NOTAMs.
+pub fn is_runway_closed(runway: &AvwxIcaoRunway, notams: &[Notam]) -> bool {
+    notams.iter()
+        .flat_map(|notam| notam.closed_runways())
+        .any(|closed| closed == runway.ident1 || closed == runway.ident2)
+}
+
+// Only `RWY <ident> CLSD` counts, So a closed ILS or taxiway on a runway doesn't close the runway
+fn closed_runways(words: &[String]) -> Vec<String> {
+    words.windows(3)
+        .filter(|triple| triple[0] == "RWY" && (triple[2] == "CLSD" || triple[2] == "CLOSED"))
+        .flat_map(|triple| triple[1].split('/').map(String::from).collect::<Vec<String>>())
+        .filter(|ident| is_runway_ident(ident))
+        .collect()
+}
+
+// Uppercase words without the punctuation around them, `U/S` and `09/27` stay intact
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|character: char| !character.is_ascii_alphanumeric() && character != '/').to_uppercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+// Like 09, 18L or 36R
+fn is_runway_ident(ident: &str) -> bool {
+    let number = ident.trim_end_matches(&['L', 'C', 'R'][..]);
+
+    (1..=2).contains(&number.len()) && ident.len() - number.len() <= 1
+        && number.parse::<u8>().map(|number| (1..=36).contains(&number)).unwrap_or(false)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FaaNotamResponse {
+    pub items: Vec<FaaNotamItem>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FaaNotamItem {
+    pub properties: FaaNotamProperties,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FaaNotamProperties {
+    pub core_notam_data: FaaCoreNotamData,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FaaCoreNotamData {
+    pub notam: FaaNotam,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FaaNotam {
+    pub id: String,
+    pub number: Option<String>,
+    pub effective_start: Option<String>,
+    pub effective_end: Option<String>,
+    pub text: String,
+}
+
+impl From<FaaNotam> for Notam {
+    fn from(faa_notam: FaaNotam) -> Self {
+        Notam::new(
+            faa_notam.number.unwrap_or(faa_notam.id),
+            faa_notam.text,
+            faa_notam.effective_start,
+            faa_notam.effective_end,
+        )
+    }
+}
@@ -0,0 +1,121 @@
+use serde::Deserialize;
+use crate::models::avwx::{AvwxIcao, AvwxIcaoRunway};
+
+/// A row of `airport-frequencies.csv` from OurAirports.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OurAirportsFrequency {
+    pub airport_ident: String,
+    #[serde(rename = "type")]
+    pub frequency_type: String,
+    pub description: String,
+    pub frequency_mhz: f32,
+}
+
+/// A row of `navaids.csv` from OurAirports.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OurAirportsNavaid {
+    pub ident: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub navaid_type: String,
+    pub frequency_khz: Option<f32>,
+    pub latitude_deg: f64,
+    pub longitude_deg: f64,
+    pub associated_airport: Option<String>,
+}
+
+impl OurAirportsNavaid {
+    /// VORs and the like are given in MHz, NDBs in kHz.
+    pub fn format_frequency(&self) -> String {
+        match self.frequency_khz {
+            Some(frequency_khz) if frequency_khz >= 10_000.0 => format!("{:.2} MHz", frequency_khz / 1000.0),
+            Some(frequency_khz) => format!("{:.0} kHz", frequency_khz),
+            None => String::from("No frequency"),
+        }
+    }
+}
+
+/// A row of `airports.csv` from OurAirports.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OurAirportsAirport {
+    pub ident: String,
+    #[serde(rename = "type")]
+    pub airport_type: String,
+    pub name: String,
+    pub latitude_deg: f32,
+    pub longitude_deg: f32,
+    pub elevation_ft: Option<i32>,
+    pub iso_country: String,
+    pub municipality: Option<String>,
+    pub gps_code: Option<String>,
+    pub iata_code: Option<String>,
+    pub home_link: Option<String>,
+    pub wikipedia_link: Option<String>,
+}
+
+/// A row of `runways.csv` from OurAirports.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OurAirportsRunway {
+    pub airport_ident: String,
+    pub length_ft: Option<i32>,
+    pub width_ft: Option<i32>,
+    pub surface: Option<String>,
+    pub lighted: u8,
+    pub closed: u8,
+    pub le_ident: String,
+    #[serde(rename = "le_heading_degT")]
+    pub le_heading_deg: Option<f32>,
+    pub he_ident: String,
+    #[serde(rename = "he_heading_degT")]
+    pub he_heading_deg: Option<f32>,
+}
+
+impl OurAirportsAirport {
+    pub fn into_station(self, runways: Vec<AvwxIcaoRunway>) -> AvwxIcao {
+        let elevation_ft = self.elevation_ft.unwrap_or(0);
+        let ourairports_link = format!("https://ourairports.com/airports/{}/", self.ident);
+
+        AvwxIcao {
+            city: self.municipality.unwrap_or_default(),
+            country: self.iso_country,
+            elevation_ft,
+            iata: self.iata_code.unwrap_or_default(),
+            wiki: self.wikipedia_link.unwrap_or(ourairports_link),
+            icao: self.ident,
+            latitude: self.latitude_deg,
+            longitude: self.longitude_deg,
+            name: self.name,
+            note: None,
+            // OurAirports doesn't know which stations report weather
+            reporting: false,
+            airport_type: self.airport_type,
+            website: self.home_link,
+            runways,
+        }
+    }
+}
+
+impl From<OurAirportsRunway> for AvwxIcaoRunway {
+    fn from(runway: OurAirportsRunway) -> Self {
+        // Runway numbers are the magnetic heading, Which is close enough when the true heading is missing
+        let le_heading = runway.le_heading_deg.or_else(|| heading_from_ident(&runway.le_ident)).unwrap_or(0.0);
+        let he_heading = runway.he_heading_deg.or_else(|| heading_from_ident(&runway.he_ident)).unwrap_or((le_heading + 180.0) % 360.0);
+
+        AvwxIcaoRunway {
+            length_ft: runway.length_ft.unwrap_or(0),
+            width_ft: runway.width_ft.unwrap_or(0),
+            surface: runway.surface.unwrap_or_default(),
+            lights: runway.lighted == 1,
+            ident1: runway.le_ident,
+            ident2: runway.he_ident,
+            bearing1: le_heading,
+            bearing2: he_heading,
+        }
+    }
+}
+
+fn heading_from_ident(ident: &str) -> Option<f32> {
+    ident.trim_end_matches(&['L', 'C', 'R'][..])
+        .parse::<f32>().ok()
+        .map(|number| number * 10.0)
+}
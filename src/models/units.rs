@@ -0,0 +1,133 @@
+use serde::{Serialize, Deserialize};
+use crate::models::metar::Altimeter;
+
+/// The units a user wants the aviation embeds in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct UserUnits {
+    pub altitude: AltitudeUnit,
+    pub speed: SpeedPreference,
+    pub pressure: PressureUnit,
+    pub temperature: TemperatureUnit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum AltitudeUnit {
+    Feet,
+    Meters,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SpeedPreference {
+    Knots,
+    KilometersPerHour,
+    MilesPerHour,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PressureUnit {
+    Hectopascals,
+    InchesOfMercury,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+impl Default for UserUnits {
+    fn default() -> Self {
+        UserUnits {
+            altitude: AltitudeUnit::Feet,
+            speed: SpeedPreference::Knots,
+            pressure: PressureUnit::Hectopascals,
+            temperature: TemperatureUnit::Celsius,
+        }
+    }
+}
+
+impl UserUnits {
+    /// Sets whichever unit the name belongs to, Returns false when it isn't a known unit.
+    pub fn set(&mut self, name: &str) -> bool {
+        match name.to_lowercase().trim_start_matches('°') {
+            "ft" | "feet" => self.altitude = AltitudeUnit::Feet,
+            "m" | "meters" | "metres" => self.altitude = AltitudeUnit::Meters,
+            "kt" | "kts" | "knots" => self.speed = SpeedPreference::Knots,
+            "kmh" | "km/h" | "kph" => self.speed = SpeedPreference::KilometersPerHour,
+            "mph" => self.speed = SpeedPreference::MilesPerHour,
+            "hpa" | "mb" | "qnh" => self.pressure = PressureUnit::Hectopascals,
+            "inhg" | "in" => self.pressure = PressureUnit::InchesOfMercury,
+            "c" | "celsius" => self.temperature = TemperatureUnit::Celsius,
+            "f" | "fahrenheit" => self.temperature = TemperatureUnit::Fahrenheit,
+            _ => return false,
+        }
+
+        true
+    }
+
+    pub fn format_altitude(&self, feet: f32) -> String {
+        match self.altitude {
+            AltitudeUnit::Feet => format!("{:.0} {}", feet, self.altitude_abbreviation()),
+            AltitudeUnit::Meters => format!("{:.0} {}", feet * 0.3048, self.altitude_abbreviation()),
+        }
+    }
+
+    pub fn format_speed(&self, knots: f32) -> String {
+        format!("{:.0} {}", self.speed_from_knots(knots), self.speed_abbreviation())
+    }
+
+    pub fn speed_from_knots(&self, knots: f32) -> f32 {
+        match self.speed {
+            SpeedPreference::Knots => knots,
+            SpeedPreference::KilometersPerHour => knots * 1.852,
+            SpeedPreference::MilesPerHour => knots * 1.150_779,
+        }
+    }
+
+    pub fn speed_to_knots(&self, speed: f32) -> f32 {
+        speed / self.speed_from_knots(1.0)
+    }
+
+    pub fn format_pressure(&self, altimeter: Altimeter) -> String {
+        match self.pressure {
+            PressureUnit::Hectopascals => format!("{:.0} {}", altimeter.as_hectopascals(), self.pressure_abbreviation()),
+            PressureUnit::InchesOfMercury => format!("{:.2} {}", altimeter.as_inches_of_mercury(), self.pressure_abbreviation()),
+        }
+    }
+
+    pub fn format_temperature(&self, celsius: f32) -> String {
+        match self.temperature {
+            TemperatureUnit::Celsius => format!("{:.0} {}", celsius, self.temperature_abbreviation()),
+            TemperatureUnit::Fahrenheit => format!("{:.0} {}", celsius * 9.0 / 5.0 + 32.0, self.temperature_abbreviation()),
+        }
+    }
+
+    pub fn altitude_abbreviation(&self) -> &'static str {
+        match self.altitude {
+            AltitudeUnit::Feet => "ft",
+            AltitudeUnit::Meters => "m",
+        }
+    }
+
+    pub fn speed_abbreviation(&self) -> &'static str {
+        match self.speed {
+            SpeedPreference::Knots => "kt",
+            SpeedPreference::KilometersPerHour => "km/h",
+            SpeedPreference::MilesPerHour => "mph",
+        }
+    }
+
+    pub fn pressure_abbreviation(&self) -> &'static str {
+        match self.pressure {
+            PressureUnit::Hectopascals => "hPa",
+            PressureUnit::InchesOfMercury => "inHg",
+        }
+    }
+
+    pub fn temperature_abbreviation(&self) -> &'static str {
+        match self.temperature {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+}